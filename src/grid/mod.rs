@@ -1,3 +1,4 @@
+pub mod moves;
 pub mod parser;
 pub mod solver;
 
@@ -5,13 +6,27 @@ pub mod solver;
 mod tests;
 
 use crate::complex::Complex;
-use rand::{seq::IteratorRandom, thread_rng};
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    thread_rng, Rng,
+};
+use solver::solvability;
 use std::{collections::HashSet, fmt};
 
+// where the tiles go in the goal, the blank ending up in the center of the
+// snail, or last otherwise
+#[derive(Clone, Copy)]
+pub enum Layout {
+    Snail,
+    RowMajor,
+    ColumnMajor,
+}
+
 #[derive(Clone)]
 pub struct Grid {
     v: Vec<i32>,
-    size: i32,
+    width: i32,
+    height: i32,
     zero: Complex<i32>,
 }
 
@@ -25,12 +40,27 @@ impl Grid {
         ]
     }
 
+    // the direction the blank moves in
+    fn letter(d: Complex<i32>) -> char {
+        match (d.x, d.y) {
+            (0, -1) => 'U',
+            (0, 1) => 'D',
+            (-1, 0) => 'L',
+            (1, 0) => 'R',
+            _ => unreachable!(),
+        }
+    }
+
+    fn rows(&self) -> Vec<&[i32]> {
+        self.v.chunks(self.width as usize).collect()
+    }
+
     fn get_cell_ref(&self, p: Complex<i32>) -> &i32 {
-        &self.v[(p.y * self.size + p.x) as usize]
+        &self.v[(p.y * self.width + p.x) as usize]
     }
 
     fn get_cell_mut(&mut self, p: Complex<i32>) -> &mut i32 {
-        &mut self.v[(p.y * self.size + p.x) as usize]
+        &mut self.v[(p.y * self.width + p.x) as usize]
     }
 
     fn op(&mut self, d: Complex<i32>) {
@@ -41,36 +71,62 @@ impl Grid {
         self.zero += d;
     }
 
+    fn contains(&self, p: Complex<i32>) -> bool {
+        (0..self.width).contains(&p.x) && (0..self.height).contains(&p.y)
+    }
+
     fn is_op_legal(&self, d: Complex<i32>) -> bool {
-        let p = self.zero + d;
-        (0..self.size).contains(&p.x) && (0..self.size).contains(&p.y)
+        self.contains(self.zero + d)
     }
 
-    pub fn create_solved_grid(size: i32) -> Self {
+    pub fn dims(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    pub fn create_solved_grid(width: i32, height: i32) -> Self {
         let mut grid = Grid {
-            v: vec![0; (size * size) as usize],
-            size,
+            v: vec![0; (width * height) as usize],
+            width,
+            height,
             zero: Complex::new(0, 0),
         };
         let mut hs = HashSet::new();
         let mut p = Complex::new(0, 0);
         let mut d = Complex::new(1, 0);
-        for i in 1..size * size {
+        for i in 1..width * height {
             *grid.get_cell_mut(p) = i;
             hs.insert(p);
-            p += d;
             let np = p + d;
-            if !(0..size).contains(&np.x) || !(0..size).contains(&np.y) || hs.contains(&np) {
+            if !grid.contains(np) || hs.contains(&np) {
                 d *= Complex::new(0, 1);
             }
+            p += d;
         }
         grid.zero = p;
         grid
     }
 
-    pub fn create_random_grid(size: i32, n: i32) -> Self {
-        let mut g = Self::create_solved_grid(size);
-        if size == 1 {
+    pub fn create_goal(width: i32, height: i32, layout: Layout) -> Self {
+        let cells = width * height;
+        let tile = |i: i32| match layout {
+            Layout::RowMajor => (i + 1) % cells,
+            _ => ((i % width) * height + i / width + 1) % cells,
+        };
+        match layout {
+            Layout::Snail => Self::create_solved_grid(width, height),
+            _ => Grid {
+                v: (0..cells).map(tile).collect(),
+                width,
+                height,
+                zero: Complex::new(width - 1, height - 1),
+            },
+        }
+    }
+
+    // `n` random moves away from this grid
+    pub fn scramble(&self, n: i32) -> Self {
+        let mut g = self.clone();
+        if g.v.len() == 1 {
             return g;
         }
         let mut rng = thread_rng();
@@ -85,12 +141,53 @@ impl Grid {
         }
         g
     }
+
+    // A uniformly random arrangement that can reach this grid or, when not
+    // `solvable`, one that cannot. None when no such arrangement exists.
+    pub fn shuffle(&self, solvable: bool) -> Option<Self> {
+        let mut rng = thread_rng();
+        let mut g = self.clone();
+        if g.width == 1 || g.height == 1 {
+            let tiles = self.v.iter().filter(|&&n| n != 0).copied();
+            if solvable {
+                // only the blank moves, the tiles keep their order
+                let z = rng.gen_range(0..g.v.len());
+                g.v = tiles.collect();
+                g.v.insert(z, 0);
+            } else if tiles.count() < 2 {
+                return None;
+            } else {
+                while g
+                    .v
+                    .iter()
+                    .filter(|&&n| n != 0)
+                    .eq(self.v.iter().filter(|&&n| n != 0))
+                {
+                    g.v.shuffle(&mut rng);
+                }
+            }
+        } else {
+            g.v.shuffle(&mut rng);
+            // swapping two tiles away from the blank flips the parity and is
+            // its own inverse, so both halves stay uniform
+            if solvability(&g, self).is_ok() != solvable {
+                let (a, b) = match g.v[0] == 0 || g.v[1] == 0 {
+                    true => (2, 3),
+                    false => (0, 1),
+                };
+                g.v.swap(a, b);
+            }
+        }
+        let z = g.v.iter().position(|&n| n == 0).unwrap() as i32;
+        g.zero = Complex::new(z % g.width, z / g.width);
+        Some(g)
+    }
 }
 
 impl fmt::Debug for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..self.size {
-            for x in 0..self.size {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 let value = self.get_cell_ref(Complex::new(x, y));
                 if *value == 0 {
                     write!(f, "\x1b[32m[{}]\x1b[0m\t", value)?;
@@ -98,8 +195,8 @@ impl fmt::Debug for Grid {
                     write!(f, "[{}]\t", value)?;
                 }
             }
-            if y < self.size - 1 {
-                write!(f, "\n")?;
+            if y < self.height - 1 {
+                writeln!(f)?;
             }
         }
         Ok(())
@@ -0,0 +1,106 @@
+use super::Grid;
+use crate::complex::Complex;
+use std::{error::Error, fmt};
+
+// U/D/L/R move the blank, a number moves that tile into the blank
+#[derive(Clone, Copy, PartialEq)]
+pub enum Move {
+    Blank(Complex<i32>),
+    Tile(i32),
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Move::Blank(d) => write!(f, "{}", Grid::letter(*d)),
+            Move::Tile(tile) => write!(f, "{}", tile),
+        }
+    }
+}
+
+pub enum MoveError {
+    Unknown { index: usize, text: String },
+    Illegal { index: usize, mv: Move },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::Unknown { index, text } => {
+                write!(f, "unknown move `{}` at index {}", text, index)
+            }
+            MoveError::Illegal { index, mv } => {
+                write!(f, "illegal move `{}` at index {}", mv, index)
+            }
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+impl fmt::Debug for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", *self)
+    }
+}
+
+#[cfg(test)]
+mod tests;
+
+// letters may be glued together, tile numbers are separated by spaces or commas
+pub fn parse_moves(s: &str) -> Result<Vec<Move>, MoveError> {
+    if s.contains(|c: char| c.is_ascii_digit()) {
+        return s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|w| !w.is_empty())
+            .enumerate()
+            .map(|(index, w)| {
+                w.parse().map(Move::Tile).map_err(|_| MoveError::Unknown {
+                    index,
+                    text: w.to_string(),
+                })
+            })
+            .collect();
+    }
+    s.chars()
+        .filter(|c| !c.is_whitespace())
+        .enumerate()
+        .map(|(index, c)| {
+            Grid::dirs()
+                .into_iter()
+                .find(|d| Grid::letter(*d) == c)
+                .map(Move::Blank)
+                .ok_or(MoveError::Unknown {
+                    index,
+                    text: c.to_string(),
+                })
+        })
+        .collect()
+}
+
+pub fn to_letters(sequence: &[Complex<i32>]) -> String {
+    sequence.iter().map(|d| Grid::letter(*d)).collect()
+}
+
+impl Grid {
+    // stops at the first illegal move, leaving the grid as it was before it
+    pub fn replay(&mut self, moves: &[Move]) -> Result<(), MoveError> {
+        for (index, mv) in moves.iter().enumerate() {
+            let d = match *mv {
+                Move::Blank(d) => Some(d),
+                Move::Tile(tile) => Grid::dirs()
+                    .into_iter()
+                    .find(|d| self.is_op_legal(*d) && *self.get_cell_ref(self.zero + *d) == tile),
+            };
+            match d {
+                Some(d) if self.is_op_legal(d) => self.op(d),
+                _ => return Err(MoveError::Illegal { index, mv: *mv }),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_solved(&self, goal: &Grid) -> bool {
+        self.v == goal.v
+    }
+}
@@ -0,0 +1,63 @@
+use super::{parse_moves, to_letters, Move, MoveError};
+use crate::{complex::Complex, grid::Grid};
+
+fn grid() -> Grid {
+    Grid {
+        width: 3,
+        height: 3,
+        v: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+        zero: Complex::new(2, 1),
+    }
+}
+
+#[test]
+fn letters() {
+    let moves = parse_moves("UL DR").unwrap();
+    assert!(moves.len() == 4);
+    assert!(moves[0] == Move::Blank(Complex::new(0, -1)));
+    let sequence = [
+        Complex::new(0, -1),
+        Complex::new(-1, 0),
+        Complex::new(0, 1),
+        Complex::new(1, 0),
+    ];
+    assert!(to_letters(&sequence) == "ULDR");
+    assert!(matches!(
+        parse_moves("ULX"),
+        Err(MoveError::Unknown { index: 2, .. })
+    ));
+}
+
+#[test]
+fn tiles() {
+    let moves = parse_moves("4, 3 2").unwrap();
+    assert!(moves == [Move::Tile(4), Move::Tile(3), Move::Tile(2)]);
+    assert!(matches!(
+        parse_moves("4 x"),
+        Err(MoveError::Unknown { index: 1, .. })
+    ));
+}
+
+#[test]
+fn replay() {
+    let mut g = grid();
+    g.replay(&parse_moves("L").unwrap()).unwrap();
+    assert!(g.is_solved(&Grid::create_solved_grid(3, 3)));
+
+    let mut g = grid();
+    g.replay(&parse_moves("4").unwrap()).unwrap();
+    assert!(g.is_solved(&Grid::create_solved_grid(3, 3)));
+
+    assert!(matches!(
+        grid().replay(&parse_moves("UR").unwrap()),
+        Err(MoveError::Illegal { index: 1, .. })
+    ));
+    assert!(matches!(
+        grid().replay(&parse_moves("4 1").unwrap()),
+        Err(MoveError::Illegal { index: 1, .. })
+    ));
+
+    let mut g = grid();
+    g.replay(&parse_moves("UD").unwrap()).unwrap();
+    assert!(!g.is_solved(&Grid::create_solved_grid(3, 3)));
+}
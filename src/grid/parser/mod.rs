@@ -1,13 +1,100 @@
 use super::Grid;
 use crate::complex::Complex;
-use std::{collections::HashSet, error::Error, fmt};
+use std::{collections::HashMap, error::Error, fmt};
 
+// where an error is in the input, `text` being the whole line
 #[derive(Clone)]
-pub struct ParsingError;
+pub struct Span {
+    line: usize,
+    column: usize,
+    len: usize,
+    text: String,
+}
+
+#[derive(Clone)]
+pub enum ParsingError {
+    MissingSize(Span),
+    NotANumber(Span),
+    NonPositiveSize(Span),
+    TooLarge(Span),
+    RowLength {
+        span: Span,
+        expected: usize,
+        found: usize,
+    },
+    RowCount {
+        span: Span,
+        expected: usize,
+        found: usize,
+    },
+    DuplicatedTile {
+        span: Span,
+        tile: i32,
+        first: usize,
+    },
+    OutOfRange {
+        span: Span,
+        tile: i32,
+        max: i32,
+    },
+}
+
+impl ParsingError {
+    fn span(&self) -> &Span {
+        match self {
+            ParsingError::MissingSize(span)
+            | ParsingError::NotANumber(span)
+            | ParsingError::NonPositiveSize(span)
+            | ParsingError::TooLarge(span)
+            | ParsingError::RowLength { span, .. }
+            | ParsingError::RowCount { span, .. }
+            | ParsingError::DuplicatedTile { span, .. }
+            | ParsingError::OutOfRange { span, .. } => span,
+        }
+    }
+}
 
 impl fmt::Display for ParsingError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "parsing error")
+        let span = self.span();
+        write!(f, "line {}, column {} : ", span.line, span.column)?;
+        match self {
+            ParsingError::MissingSize(_) => write!(
+                f,
+                "expected the size, or the width and the height, alone on the first line"
+            ),
+            ParsingError::NotANumber(_) => write!(f, "not a number"),
+            ParsingError::NonPositiveSize(_) => write!(f, "the size must be positive"),
+            ParsingError::TooLarge(_) => write!(f, "too many tiles"),
+            ParsingError::RowLength {
+                expected, found, ..
+            } => write!(
+                f,
+                "expected {} tiles on this row, found {}",
+                expected, found
+            ),
+            ParsingError::RowCount {
+                expected, found, ..
+            } => write!(f, "expected {} rows, found {}", expected, found),
+            ParsingError::DuplicatedTile { tile, first, .. } => {
+                write!(f, "tile {} already appears on line {}", tile, first)
+            }
+            ParsingError::OutOfRange { tile, max, .. } => {
+                write!(f, "tile {} is not between 0 and {}", tile, max)
+            }
+        }?;
+
+        // the caret keeps the tabs of the line so that it stays aligned
+        let pad = span
+            .text
+            .chars()
+            .take(span.column - 1)
+            .map(|c| match c {
+                '\t' => '\t',
+                _ => ' ',
+            })
+            .collect::<String>();
+        write!(f, "\n{}\n{}{}", span.text, pad, "^".repeat(span.len.max(1)))
     }
 }
 
@@ -22,55 +109,151 @@ impl fmt::Debug for ParsingError {
 #[cfg(test)]
 mod tests;
 
-pub fn parse(raw: &str) -> Result<Grid, ParsingError> {
-    let mut it = raw
-        .lines()
-        .map(str::trim)
-        .filter(|s| s.len() > 0)
-        .filter(|s| !s.starts_with("#"))
-        .map(|s| s.split("#").next().unwrap().split_whitespace());
+struct Token<'a> {
+    word: &'a str,
+    span: Span,
+}
 
-    if it.clone().count() < 2 {
-        return Err(ParsingError);
+// the words of every line that is not empty once comments are removed
+fn tokenize(raw: &str) -> Vec<(Span, Vec<Token<'_>>)> {
+    let mut lines = Vec::new();
+    for (i, text) in raw.lines().enumerate() {
+        let content = text.split('#').next().unwrap();
+        let mut tokens = Vec::new();
+        let mut start = None;
+        for (j, c) in content.char_indices().chain([(content.len(), ' ')]) {
+            match (c.is_whitespace(), start) {
+                (false, None) => start = Some(j),
+                (true, Some(s)) => {
+                    tokens.push(Token {
+                        word: &content[s..j],
+                        span: Span {
+                            line: i + 1,
+                            column: content[..s].chars().count() + 1,
+                            len: content[s..j].chars().count(),
+                            text: text.to_string(),
+                        },
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if !tokens.is_empty() {
+            let end = Span {
+                line: i + 1,
+                column: content.trim_end().chars().count() + 1,
+                len: 1,
+                text: text.to_string(),
+            };
+            lines.push((end, tokens));
+        }
     }
+    lines
+}
 
-    let size_it = it.next().unwrap();
-    if size_it.clone().count() != 1 {
-        return Err(ParsingError);
-    }
-    let size = match size_it.clone().next().unwrap().parse::<i32>() {
-        Ok(n) if n >= 1 => n,
-        _ => return Err(ParsingError),
+// the first line holds the size of a square grid, or its width then height
+pub fn parse(raw: &str) -> Result<Grid, ParsingError> {
+    let lines = tokenize(raw);
+    let Some(((_, size_line), rows)) = lines.split_first() else {
+        return Err(ParsingError::MissingSize(Span {
+            line: raw.lines().count().max(1),
+            column: 1,
+            len: 1,
+            text: raw.lines().last().unwrap_or("").to_string(),
+        }));
     };
-
-    if it.clone().any(|l| l.count() != size as usize) {
-        return Err(ParsingError);
-    }
-
-    let it = it.flat_map(|l| l.map(|w| w.parse::<i32>()));
-
-    if it.clone().any(|e| e.is_err()) {
-        return Err(ParsingError);
+    if size_line.len() > 2 {
+        return Err(ParsingError::MissingSize(size_line[2].span.clone()));
     }
-
-    let v = it.map(|e| e.unwrap()).collect::<Vec<_>>();
-
-    if v.len() != size.pow(2) as usize {
-        return Err(ParsingError);
+    let mut dims = Vec::new();
+    for t in size_line {
+        match t.word.parse::<i32>() {
+            Ok(n) if n >= 1 => dims.push(n),
+            Ok(_) => return Err(ParsingError::NonPositiveSize(t.span.clone())),
+            Err(_) => return Err(ParsingError::NotANumber(t.span.clone())),
+        }
     }
+    let (width, height) = (dims[0], *dims.last().unwrap());
+    let Some(cells) = width.checked_mul(height) else {
+        return Err(ParsingError::TooLarge(
+            size_line.last().unwrap().span.clone(),
+        ));
+    };
+    let (n, m) = (width as usize, height as usize);
 
-    let hs = v.iter().collect::<HashSet<_>>();
-    if hs.len() != v.len() {
-        return Err(ParsingError);
+    if rows.len() > m {
+        return Err(ParsingError::RowCount {
+            span: rows[m].1[0].span.clone(),
+            expected: m,
+            found: rows.len(),
+        });
     }
-    for n in 0..size.pow(2) {
-        if !hs.contains(&n) {
-            return Err(ParsingError);
+    let mut v = Vec::new();
+    let mut seen = HashMap::new();
+    for (end, tokens) in rows {
+        if tokens.len() != n {
+            return Err(ParsingError::RowLength {
+                span: tokens.get(n).map_or(end, |t| &t.span).clone(),
+                expected: n,
+                found: tokens.len(),
+            });
+        }
+        for t in tokens {
+            let tile = match t.word.parse::<i32>() {
+                Ok(tile) => tile,
+                Err(_) => return Err(ParsingError::NotANumber(t.span.clone())),
+            };
+            if !(0..cells).contains(&tile) {
+                return Err(ParsingError::OutOfRange {
+                    span: t.span.clone(),
+                    tile,
+                    max: cells - 1,
+                });
+            }
+            if let Some(&first) = seen.get(&tile) {
+                return Err(ParsingError::DuplicatedTile {
+                    span: t.span.clone(),
+                    tile,
+                    first,
+                });
+            }
+            seen.insert(tile, t.span.line);
+            v.push(tile);
         }
     }
+    if rows.len() < m {
+        let (end, _) = lines.last().unwrap();
+        return Err(ParsingError::RowCount {
+            span: end.clone(),
+            expected: m,
+            found: rows.len(),
+        });
+    }
 
+    // full rows of distinct tiles in range hold every tile, the blank included
     let (zero_i, _) = v.iter().enumerate().find(|(_, n)| **n == 0).unwrap();
-    let zero = Complex::new(zero_i as i32 % size, zero_i as i32 / size);
+    let zero = Complex::new(zero_i as i32 % width, zero_i as i32 / width);
 
-    Ok(Grid { size, zero, v })
+    Ok(Grid {
+        width,
+        height,
+        zero,
+        v,
+    })
+}
+
+// the layout `parse` reads, with the tiles right-aligned
+pub fn format(grid: &Grid) -> String {
+    let width = (grid.v.len() - 1).to_string().len();
+    let mut s = match grid.width == grid.height {
+        true => format!("{}\n", grid.width),
+        false => format!("{} {}\n", grid.width, grid.height),
+    };
+    for row in grid.rows() {
+        let row: Vec<_> = row.iter().map(|n| format!("{:>width$}", n)).collect();
+        s += &row.join(" ");
+        s += "\n";
+    }
+    s
 }
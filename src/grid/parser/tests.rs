@@ -1,74 +1,201 @@
 #[cfg(test)]
 mod invalid {
-    use super::super::parse;
+    use super::super::{parse, ParsingError};
+
+    fn at(e: &ParsingError) -> (usize, usize) {
+        (e.span().line, e.span().column)
+    }
 
     #[test]
     fn duplicated_value() {
         let res = parse(include_str!("test_inputs/invalid/duplicated_value.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::DuplicatedTile {
+                tile: 2,
+                first: 2,
+                ..
+            }
+        ));
+        assert!(at(&e) == (4, 1));
     }
 
     #[test]
     fn grid_size_1() {
         let res = parse(include_str!("test_inputs/invalid/grid_size_1.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::RowLength {
+                expected: 4,
+                found: 3,
+                ..
+            }
+        ));
+        assert!(at(&e) == (2, 6));
     }
 
     #[test]
     fn grid_size_2() {
         let res = parse(include_str!("test_inputs/invalid/grid_size_2.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::RowCount {
+                expected: 2,
+                found: 3,
+                ..
+            }
+        ));
+        assert!(at(&e) == (4, 1));
     }
 
     #[test]
     fn lines_count() {
         let res = parse(include_str!("test_inputs/invalid/lines_count.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::RowCount {
+                expected: 3,
+                found: 4,
+                ..
+            }
+        ));
+        assert!(at(&e) == (5, 1));
+    }
+
+    #[test]
+    fn missing_size() {
+        let res = parse(include_str!("test_inputs/invalid/missing_size.txt"));
+        let e = res.err().unwrap();
+        assert!(matches!(e, ParsingError::MissingSize(_)));
+        assert!(at(&e) == (1, 5));
+        assert!(matches!(parse(""), Err(ParsingError::MissingSize(_))));
     }
 
     #[test]
     fn negative_size() {
         let res = parse(include_str!("test_inputs/invalid/negative_size.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(e, ParsingError::NonPositiveSize(_)));
+        assert!(at(&e) == (1, 1));
     }
 
     #[test]
     fn negative_value() {
         let res = parse(include_str!("test_inputs/invalid/negative_value.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::OutOfRange {
+                tile: -4,
+                max: 8,
+                ..
+            }
+        ));
+        assert!(at(&e) == (3, 3));
     }
 
     #[test]
     fn skipped_value() {
         let res = parse(include_str!("test_inputs/invalid/skipped_value.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::OutOfRange {
+                tile: 16,
+                max: 15,
+                ..
+            }
+        ));
+        assert!(at(&e) == (5, 4));
     }
 
     #[test]
     fn wrong_value() {
         let res = parse(include_str!("test_inputs/invalid/wrong_value.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(e, ParsingError::NotANumber(_)));
+        assert!(at(&e) == (3, 3));
     }
 
     #[test]
     fn zero_size() {
         let res = parse(include_str!("test_inputs/invalid/zero_size.txt"));
-        assert!(res.is_err());
+        let e = res.err().unwrap();
+        assert!(matches!(e, ParsingError::NonPositiveSize(_)));
+        assert!(at(&e) == (1, 1));
+    }
+
+    #[test]
+    fn too_large() {
+        let e = parse("65536 65536\n1 2\n").err().unwrap();
+        assert!(matches!(e, ParsingError::TooLarge(_)));
+        assert!(at(&e) == (1, 7));
+    }
+
+    #[test]
+    fn missing_rows() {
+        let e = parse("3\n1 2 3\n4 5 6 # comment\n").err().unwrap();
+        assert!(matches!(
+            e,
+            ParsingError::RowCount {
+                expected: 3,
+                found: 2,
+                ..
+            }
+        ));
+        assert!(at(&e) == (3, 6));
+    }
+
+    #[test]
+    fn caret() {
+        let e = parse("3\n3 2 6\n1 y 0\n8 7 5\n").err().unwrap();
+        assert!(e.to_string() == "line 3, column 3 : not a number\n1 y 0\n  ^");
+        let e = parse("3\n3\t2 6\n1 4 0\n8 7 42\n").err().unwrap();
+        assert!(e.to_string().ends_with("\n8 7 42\n    ^^"));
+        let e = parse("3\n3\t22 6\n1 4 0\n8 7 5\n").err().unwrap();
+        assert!(e.to_string().ends_with("\n3\t22 6\n \t^^"));
     }
 }
 
 #[cfg(test)]
 mod valid {
-    use super::super::parse;
-    use crate::grid::Grid;
+    use super::super::{format, parse};
+    use crate::{complex::Complex, grid::Grid};
+
+    #[test]
+    fn round_trip() {
+        for size in 1..12 {
+            let g = Grid::create_solved_grid(size, size).scramble(100);
+            let g2 = parse(&format(&g)).unwrap();
+            assert!(g2.v == g.v);
+            assert!(g2.zero == g.zero);
+        }
+        let g = parse("3\n1 2 3\n8 0 4\n7 6 5\n").unwrap();
+        assert!(format(&g) == "3\n1 2 3\n8 0 4\n7 6 5\n");
+        let g = Grid::create_solved_grid(4, 6).scramble(100);
+        assert!(parse(&format(&g)).unwrap().v == g.v);
+    }
+
+    #[test]
+    fn rectangle() {
+        let g = parse("3 2\n1 2 3\n0 5 4\n").unwrap();
+        assert!((g.width, g.height) == (3, 2));
+        assert!(g.v == Grid::create_solved_grid(3, 2).v);
+        assert!(g.zero == Complex::new(0, 1));
+        assert!(format(&g) == "3 2\n1 2 3\n0 5 4\n");
+    }
 
     #[test]
     fn big_snail() {
         let res = parse(include_str!("test_inputs/valid/big_snail.txt"));
         assert!(res.is_ok());
         let g = res.unwrap();
-        assert!(g.size == 10);
-        assert!(g.v == Grid::create_solved_grid(10).v);
+        assert!((g.width, g.height) == (10, 10));
+        assert!(g.v == Grid::create_solved_grid(10, 10).v);
     }
 
     #[test]
@@ -76,7 +203,7 @@ mod valid {
         let res = parse(include_str!("test_inputs/valid/comments.txt"));
         assert!(res.is_ok());
         let g = res.unwrap();
-        assert!(g.size == 3);
+        assert!((g.width, g.height) == (3, 3));
         assert!(g.v == vec![3, 2, 6, 1, 4, 0, 8, 7, 5]);
     }
 
@@ -85,7 +212,7 @@ mod valid {
         let res = parse(include_str!("test_inputs/valid/whitespaces.txt"));
         assert!(res.is_ok());
         let g = res.unwrap();
-        assert!(g.size == 3);
+        assert!((g.width, g.height) == (3, 3));
         assert!(g.v == vec![3, 2, 6, 1, 4, 0, 8, 7, 5]);
     }
 }
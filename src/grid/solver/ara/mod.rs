@@ -0,0 +1,143 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use super::{Hcost, Heuristic, Res, State, Stop};
+use crate::grid::Grid;
+
+#[cfg(test)]
+mod tests;
+
+// the weight is halved towards 1 after every solution
+fn lower_weight(w: f64) -> f64 {
+    match (w - 1.) / 2. + 1. {
+        w if w < 1.01 => 1.,
+        w => w,
+    }
+}
+
+// ARA* : a weighted A* that keeps its states from one weight to the next. A
+// state whose g cost drops after it was closed is not reopened but set aside
+// in `incons`, and goes back to the open set for the next, lower, weight.
+//
+// Every solution is reported with a bound on its suboptimality, the search
+// stops once the bound is 1 or, when a solution is known, once told to.
+pub(super) fn ara(
+    grid: &Grid,
+    target: &Grid,
+    h: Heuristic,
+    weight: f64,
+    stop: &Stop,
+    mut report: impl FnMut(&Res, f64),
+) -> Res {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let hcost = Hcost::new(target, h);
+    let mut nodes: HashMap<Vec<i32>, State> = HashMap::new();
+    let mut open_set: BinaryHeap<Reverse<State>> = BinaryHeap::new();
+    let mut closed_set: HashSet<Vec<i32>> = HashSet::new();
+    let mut incons: HashSet<Vec<i32>> = HashSet::new();
+    let mut w = weight.max(1.);
+    {
+        let mut state = State {
+            grid: grid.clone(),
+            h_cost: 0,
+            g_cost: 0,
+            f_cost: 0.,
+            last_op: None,
+            wd: [0; 2],
+        };
+        hcost.init_hcost(&mut state);
+        state.f_cost = w * state.h_cost as f64;
+        nodes.insert(grid.v.clone(), state.clone());
+        open_set.push(Reverse(state));
+    }
+
+    loop {
+        while let Some(Reverse(s)) = open_set.peek() {
+            let goal = nodes.get(&target.v).map(|s| s.g_cost);
+            if goal.is_some_and(|g| s.f_cost >= g as f64) {
+                break;
+            }
+            if goal.is_some() && stop.stopped().is_some() {
+                return res;
+            }
+            let s = open_set.pop().unwrap().0;
+            if closed_set.contains(&s.grid.v) || nodes[&s.grid.v].g_cost != s.g_cost {
+                continue;
+            }
+            closed_set.insert(s.grid.v.clone());
+            res.time_complexity += 1;
+            let dirs = Grid::dirs();
+            let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
+            for op in ops {
+                let mut ns = s.clone();
+                ns.grid.op(*op);
+                ns.g_cost += 1;
+                ns.last_op = Some(*op);
+                if nodes.get(&ns.grid.v).is_some_and(|n| n.g_cost <= ns.g_cost) {
+                    continue;
+                }
+                hcost.smart_hcost(&s, &mut ns);
+                ns.f_cost = ns.g_cost as f64 + w * ns.h_cost as f64;
+                nodes.insert(ns.grid.v.clone(), ns.clone());
+                match closed_set.contains(&ns.grid.v) {
+                    true => {
+                        incons.insert(ns.grid.v.clone());
+                    }
+                    false => open_set.push(Reverse(ns)),
+                }
+            }
+            res.size_complexity = res.size_complexity.max(nodes.len());
+        }
+
+        let mut g = target.clone();
+        res.sequence.clear();
+        while let Some(op) = nodes[&g.v].last_op {
+            res.sequence.push(op);
+            g.op(op * -1);
+        }
+        res.sequence.reverse();
+
+        // the states left to expand give a lower bound on the optimal cost
+        let pending = open_set
+            .iter()
+            .map(|s| &s.0.grid.v)
+            .filter(|v| !closed_set.contains(*v))
+            .chain(incons.iter())
+            .cloned()
+            .collect::<HashSet<_>>();
+        let cost = res.sequence.len() as f64;
+        let bound = match pending
+            .iter()
+            .map(|v| nodes[v].g_cost + nodes[v].h_cost)
+            .min()
+        {
+            _ if w <= 1. => 1.,
+            Some(lower) if lower > 0 => (cost / lower as f64).clamp(1., w),
+            _ => 1.,
+        };
+        report(&res, bound);
+        if bound <= 1. || stop.stopped().is_some() {
+            return res;
+        }
+
+        w = lower_weight(w.min(bound));
+        open_set = pending
+            .into_iter()
+            .map(|v| {
+                let mut s = nodes[&v].clone();
+                s.f_cost = s.g_cost as f64 + w * s.h_cost as f64;
+                Reverse(s)
+            })
+            .collect();
+        closed_set.clear();
+        incons.clear();
+    }
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use super::{ara, Stop};
+use crate::grid::{
+    solver::{solve, Algo, Heuristic},
+    Grid,
+};
+
+fn check_sequence(g: &Grid, res: &super::Res) {
+    let mut g = g.clone();
+    for &op in res.sequence.iter() {
+        assert!(g.is_op_legal(op));
+        g.op(op);
+    }
+    assert!(g.v == Grid::create_solved_grid(g.width, g.height).v);
+}
+
+#[test]
+fn improves_to_optimal() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        let optimal = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::Astar,
+            None,
+        )
+        .unwrap()
+        .sequence
+        .len();
+        let target = Grid::create_solved_grid(3, 3);
+        let mut reports = Vec::new();
+        let res = ara(
+            &g,
+            &target,
+            Heuristic::Manhattan,
+            5.,
+            &Stop::NEVER,
+            |res, bound| {
+                check_sequence(&g, res);
+                reports.push((res.sequence.len(), bound));
+            },
+        );
+        assert!(res.sequence.len() == optimal);
+        assert!(reports.last().unwrap().1 == 1.);
+        for (i, &(len, bound)) in reports.iter().enumerate() {
+            assert!(bound <= 5.);
+            assert!(len as f64 <= bound * optimal as f64 + 1e-9);
+            if i > 0 {
+                assert!(len <= reports[i - 1].0);
+                assert!(bound <= reports[i - 1].1);
+            }
+        }
+    }
+}
+
+#[test]
+fn deadline() {
+    let g = Grid::create_solved_grid(4, 4).scramble(1000);
+    let target = Grid::create_solved_grid(4, 4);
+    let mut reports = 0;
+    let res = ara(
+        &g,
+        &target,
+        Heuristic::LinearConflict,
+        5.,
+        &Stop::new(Some(Duration::ZERO)),
+        |_, _| reports += 1,
+    );
+    assert!(reports == 1);
+    check_sequence(&g, &res);
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use super::{Hcost, Heuristic, Res, State};
+use crate::{complex::Complex, grid::Grid};
+
+#[cfg(test)]
+mod tests;
+
+// states are only remembered by their hash, a collision can at worst hide a
+// state from the beam
+fn key(v: &Vec<i32>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    v.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Breadth-first search keeping only the `width` states with the smallest
+// heuristic at every depth. The layers only keep the parent and the move of
+// every state so the path can be rebuilt.
+pub(super) fn beam(grid: &Grid, target: &Grid, h: Heuristic, width: usize) -> Option<Res> {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let width = width.max(1);
+    let hcost = Hcost::new(target, h);
+    let mut start = State {
+        grid: grid.clone(),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    hcost.init_hcost(&mut start);
+    let lc = Hcost::new(target, Heuristic::LinearConflict).hcost(grid);
+    res.lower_bound = Some(start.h_cost.max(lc));
+
+    let mut seen = HashSet::from([key(&grid.v)]);
+    let mut layers: Vec<Vec<(usize, Complex<i32>)>> = Vec::new();
+    let mut layer = vec![start];
+    loop {
+        if let Some(mut i) = layer.iter().position(|s| s.grid.v == target.v) {
+            for l in layers.iter().rev() {
+                res.sequence.push(l[i].1);
+                i = l[i].0;
+            }
+            res.sequence.reverse();
+            return Some(res);
+        }
+
+        let mut next: Vec<(State, usize, u64)> = Vec::new();
+        let mut generated = HashSet::new();
+        for (i, s) in layer.iter().enumerate() {
+            res.time_complexity += 1;
+            let dirs = Grid::dirs();
+            let ops = dirs
+                .iter()
+                .filter(|d| s.grid.is_op_legal(**d) && Some(**d * -1) != s.last_op);
+            for op in ops {
+                let mut ns = s.clone();
+                ns.grid.op(*op);
+                let k = key(&ns.grid.v);
+                if seen.contains(&k) || !generated.insert(k) {
+                    continue;
+                }
+                ns.g_cost += 1;
+                ns.last_op = Some(*op);
+                hcost.smart_hcost(s, &mut ns);
+                next.push((ns, i, k));
+            }
+        }
+        if next.is_empty() {
+            return None;
+        }
+        if next.len() > width {
+            next.select_nth_unstable_by_key(width, |(s, _, _)| s.h_cost);
+            next.truncate(width);
+        }
+
+        seen.extend(next.iter().map(|(_, _, k)| *k));
+        layers.push(
+            next.iter()
+                .map(|(s, i, _)| (*i, s.last_op.unwrap()))
+                .collect(),
+        );
+        layer = next.into_iter().map(|(s, _, _)| s).collect();
+        res.size_complexity = res.size_complexity.max(seen.len() + layer.len());
+    }
+}
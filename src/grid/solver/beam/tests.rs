@@ -0,0 +1,41 @@
+use super::beam;
+use crate::grid::{
+    solver::{solve, Algo, Heuristic},
+    Grid,
+};
+
+fn check(g: &Grid, width: usize) -> usize {
+    let target = Grid::create_solved_grid(g.width, g.height);
+    let res = beam(g, &target, Heuristic::LinearConflict, width).unwrap();
+    let mut ng = g.clone();
+    for &op in res.sequence.iter() {
+        assert!(ng.is_op_legal(op));
+        ng.op(op);
+    }
+    assert!(ng.v == target.v);
+    assert!(res.sequence.len() as i32 >= res.lower_bound.unwrap());
+    res.sequence.len()
+}
+
+#[test]
+fn wide_beam_is_optimal() {
+    // a beam wider than the whole 3x3 state space is a breadth-first search
+    for _ in 0..2 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        let optimal = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::Astar,
+            None,
+        )
+        .unwrap();
+        assert!(check(&g, 200_000) == optimal.sequence.len());
+    }
+}
+
+#[test]
+fn large_grid() {
+    let g = Grid::create_solved_grid(10, 10).scramble(1000);
+    check(&g, 100);
+}
@@ -0,0 +1,161 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use super::{Hcost, Heuristic, Res, State};
+use crate::{complex::Complex, grid::Grid};
+
+#[cfg(test)]
+mod tests;
+
+type Heap = BinaryHeap<Reverse<(i32, usize, i32)>>;
+
+// One side of the search. Nodes are never removed and the heap is cleaned up
+// lazily : an entry is stale once its node is closed or reached again with a
+// smaller g cost.
+struct Frontier {
+    hcost: Hcost,
+    back: Hcost,
+    nodes: Vec<(State, bool)>,
+    index: HashMap<Vec<i32>, usize>,
+    open_set: Heap,
+}
+
+impl Frontier {
+    fn new(start: &Grid, target: &Grid, h: Heuristic) -> Self {
+        let mut frontier = Self {
+            hcost: Hcost::new(target, h),
+            back: Hcost::new(start, h),
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            open_set: BinaryHeap::new(),
+        };
+        let mut state = State {
+            grid: start.clone(),
+            h_cost: 0,
+            g_cost: 0,
+            f_cost: 0.,
+            last_op: None,
+            wd: [0; 2],
+        };
+        frontier.hcost.init_hcost(&mut state);
+        frontier.push(state);
+        frontier
+    }
+
+    // b = 2g + h - h_back, the half-sum of the smallest b of both sides is a
+    // lower bound on any path that has not been found yet
+    fn push(&mut self, state: State) {
+        let b = 2 * state.g_cost + state.h_cost - self.back.hcost(&state.grid);
+        let g = state.g_cost;
+        let id = match self.index.get(&state.grid.v) {
+            Some(&id) => {
+                self.nodes[id] = (state, true);
+                id
+            }
+            None => {
+                self.index.insert(state.grid.v.clone(), self.nodes.len());
+                self.nodes.push((state, true));
+                self.nodes.len() - 1
+            }
+        };
+        self.open_set.push(Reverse((b, id, g)));
+    }
+
+    fn g_cost(&self, v: &Vec<i32>) -> Option<i32> {
+        self.index.get(v).map(|&id| self.nodes[id].0.g_cost)
+    }
+
+    fn min(&mut self) -> Option<(i32, usize)> {
+        while let Some(&Reverse((b, id, g))) = self.open_set.peek() {
+            let (s, open) = &self.nodes[id];
+            if *open && s.g_cost == g {
+                return Some((b, id));
+            }
+            self.open_set.pop();
+        }
+        None
+    }
+
+    fn path(&self, mut g: Grid) -> Vec<Complex<i32>> {
+        let mut sequence = Vec::new();
+        while let Some(op) = self.nodes[self.index[&g.v]].0.last_op {
+            sequence.push(op);
+            g.op(op * -1);
+        }
+        sequence
+    }
+}
+
+// BAE* : both sides are front-to-end searches ordered by their b value, the
+// search stops once the best path found so far is below the lower bound given
+// by the two frontiers.
+pub(super) fn bidirectional(grid: &Grid, target: &Grid, h: Heuristic) -> Res {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let mut sides = [
+        Frontier::new(grid, target, h),
+        Frontier::new(target, grid, h),
+    ];
+    let mut best = (grid.v == target.v).then(|| (0, grid.clone()));
+
+    while let (Some((fw, fw_id)), Some((bw, bw_id))) = (sides[0].min(), sides[1].min()) {
+        if best.as_ref().is_some_and(|(u, _)| 2 * u <= fw + bw) {
+            break;
+        }
+
+        let [forward, backward] = &mut sides;
+        let (frontier, other, id) = match bw < fw {
+            false => (forward, &*backward, fw_id),
+            true => (backward, &*forward, bw_id),
+        };
+        frontier.nodes[id].1 = false;
+        let s = frontier.nodes[id].0.clone();
+        res.time_complexity += 1;
+
+        let dirs = Grid::dirs();
+        let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
+        for op in ops {
+            let mut ns = s.clone();
+            ns.grid.op(*op);
+            ns.g_cost += 1;
+            ns.last_op = Some(*op);
+            if frontier.g_cost(&ns.grid.v).is_some_and(|g| g <= ns.g_cost) {
+                continue;
+            }
+            frontier.hcost.smart_hcost(&s, &mut ns);
+            let meets = other
+                .g_cost(&ns.grid.v)
+                .map(|g| ns.g_cost + g)
+                .filter(|&c| best.as_ref().is_none_or(|(u, _)| c < *u));
+            if let Some(c) = meets {
+                best = Some((c, ns.grid.clone()));
+            }
+            // apart from the meeting point, such a node cannot be part of a
+            // shorter path anymore
+            if meets.is_some()
+                || best
+                    .as_ref()
+                    .is_none_or(|(u, _)| ns.g_cost + ns.h_cost < *u)
+            {
+                frontier.push(ns);
+            }
+        }
+        res.size_complexity = res
+            .size_complexity
+            .max(sides[0].nodes.len() + sides[1].nodes.len());
+    }
+
+    let (_, meeting) = best.unwrap();
+    res.sequence = sides[0].path(meeting.clone());
+    res.sequence.reverse();
+    res.sequence
+        .extend(sides[1].path(meeting).into_iter().map(|op| op * -1));
+    res
+}
@@ -0,0 +1,35 @@
+use super::bidirectional;
+use crate::grid::{
+    solver::{tests::check_optimal, Heuristic},
+    Grid,
+};
+
+fn check(g: &Grid, h: Heuristic) {
+    let res = bidirectional(g, &Grid::create_solved_grid(g.width, g.height), h);
+    check_optimal(g, &res);
+}
+
+#[test]
+fn optimal() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        check(&g, Heuristic::Manhattan);
+        check(&g, Heuristic::LinearConflict);
+    }
+    check(
+        &Grid::create_solved_grid(3, 3).scramble(20),
+        Heuristic::Zero,
+    );
+}
+
+#[test]
+fn arbitrary_target() {
+    let start = Grid::create_solved_grid(3, 3).scramble(100);
+    let target = Grid::create_solved_grid(3, 3).scramble(100);
+    let res = bidirectional(&start, &target, Heuristic::Manhattan);
+    let mut g = start.clone();
+    for &op in res.sequence.iter() {
+        g.op(op);
+    }
+    assert!(g.v == target.v);
+}
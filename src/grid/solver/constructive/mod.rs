@@ -0,0 +1,343 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, VecDeque},
+};
+
+use super::{astar, sub_puzzle, Heuristic, Res, Stop};
+use crate::{complex::Complex, grid::Grid};
+
+#[cfg(test)]
+mod tests;
+
+struct Builder<'a> {
+    grid: Grid,
+    target: &'a Grid,
+    fixed: Vec<bool>,
+    // where every tile is
+    pos: Vec<Complex<i32>>,
+    // path search scratch space, a cell is reached in the current search when
+    // its stamp is `run`
+    stamp: Vec<u32>,
+    cost: Vec<i32>,
+    from: Vec<Complex<i32>>,
+    open: BinaryHeap<Reverse<(i32, i32, usize)>>,
+    run: u32,
+    res: Res,
+}
+
+impl Builder<'_> {
+    fn index(&self, p: Complex<i32>) -> usize {
+        (p.y * self.grid.width + p.x) as usize
+    }
+
+    fn is_free(&self, p: Complex<i32>) -> bool {
+        self.grid.contains(p) && !self.fixed[self.index(p)]
+    }
+
+    fn set_fixed(&mut self, p: Complex<i32>, fixed: bool) {
+        let i = self.index(p);
+        self.fixed[i] = fixed;
+    }
+
+    fn find(&self, tile: i32) -> Complex<i32> {
+        self.pos[tile as usize]
+    }
+
+    fn op(&mut self, d: Complex<i32>) {
+        let tile = *self.grid.get_cell_ref(self.grid.zero + d);
+        self.pos[tile as usize] = self.grid.zero;
+        self.pos[0] = self.grid.zero + d;
+        self.grid.op(d);
+        self.res.sequence.push(d);
+    }
+
+    // Shortest path between two cells through the free ones, as a list of
+    // steps. A* with the Manhattan distance only looks around the straight
+    // route, a breadth-first search would go over the whole rectangle.
+    fn path(
+        &mut self,
+        from: Complex<i32>,
+        to: Complex<i32>,
+        avoid: Option<Complex<i32>>,
+    ) -> Vec<Complex<i32>> {
+        self.run += 1;
+        let dist = |p: Complex<i32>| (p.x - to.x).abs() + (p.y - to.y).abs();
+        let i = self.index(from);
+        self.stamp[i] = self.run;
+        self.cost[i] = 0;
+        // the deepest cell first among the ones with the same estimate
+        self.open.clear();
+        self.open.push(Reverse((dist(from), 0, i)));
+        while let Some(Reverse((_, depth, i))) = self.open.pop() {
+            let g = -depth;
+            if g > self.cost[i] {
+                continue;
+            }
+            self.res.time_complexity += 1;
+            let p = Complex::new(i as i32 % self.grid.width, i as i32 / self.grid.width);
+            if p == to {
+                break;
+            }
+            for d in Grid::dirs() {
+                let np = p + d;
+                if !self.is_free(np) || Some(np) == avoid {
+                    continue;
+                }
+                let j = self.index(np);
+                if self.stamp[j] == self.run && self.cost[j] <= g + 1 {
+                    continue;
+                }
+                self.stamp[j] = self.run;
+                self.cost[j] = g + 1;
+                self.from[j] = d;
+                self.open.push(Reverse((g + 1 + dist(np), -(g + 1), j)));
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut p = to;
+        while p != from {
+            let d = self.from[self.index(p)];
+            steps.push(d);
+            p += d * -1;
+        }
+        steps.reverse();
+        steps
+    }
+
+    fn move_blank(&mut self, to: Complex<i32>, avoid: Option<Complex<i32>>) {
+        for d in self.path(self.grid.zero, to, avoid) {
+            self.op(d);
+        }
+    }
+
+    // Brings the blank next to the tile at `p` to the cell after it along
+    // `d` without a search : two moves from the side, four from behind,
+    // when the cells on one side are free.
+    fn go_around(&mut self, p: Complex<i32>, d: Complex<i32>) -> bool {
+        let z = self.grid.zero;
+        let side = Complex::new(d.y, d.x);
+        for s in [side, side * -1] {
+            let route = if z == p + s {
+                vec![d, s * -1]
+            } else if z == p + d * -1 {
+                vec![s, d, d, s * -1]
+            } else {
+                continue;
+            };
+            let mut q = z;
+            if route.iter().all(|&m| {
+                q += m;
+                self.is_free(q)
+            }) {
+                for m in route {
+                    self.op(m);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    // the tile follows its own shortest path, the blank going around it to
+    // the next cell every time
+    fn move_tile(&mut self, tile: i32, to: Complex<i32>) {
+        let mut p = self.find(tile);
+        for d in self.path(p, to, None) {
+            if !self.go_around(p, d) {
+                self.move_blank(p + d, Some(p));
+            }
+            self.op(d * -1);
+            p += d;
+        }
+    }
+
+    // Brings two tiles to the cells `p` and `q` in the 2x3 block made of
+    // them and the two lines below, looking at every position of the two
+    // tiles and the blank in the block.
+    fn solve_block(
+        &mut self,
+        a: i32,
+        b: i32,
+        p: Complex<i32>,
+        q: Complex<i32>,
+        inward: Complex<i32>,
+    ) {
+        let block = [p, q, p + inward, q + inward, p + inward * 2, q + inward * 2];
+        let at = |c: Complex<i32>| block.iter().position(|&x| x == c).unwrap();
+        let start = (at(self.find(a)), at(self.find(b)), at(self.grid.zero));
+        let mut from = HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+        let mut end = start;
+        while let Some(s @ (ta, tb, z)) = queue.pop_front() {
+            if (ta, tb) == (0, 1) {
+                end = s;
+                break;
+            }
+            for d in Grid::dirs() {
+                let Some(nz) = block.iter().position(|&c| c == block[z] + d) else {
+                    continue;
+                };
+                let swap = |t: usize| match t == nz {
+                    true => z,
+                    false => t,
+                };
+                let ns = (swap(ta), swap(tb), nz);
+                if let Entry::Vacant(e) = from.entry(ns) {
+                    e.insert(Some((s, d)));
+                    queue.push_back(ns);
+                }
+            }
+        }
+
+        let mut steps = Vec::new();
+        while let Some((s, d)) = from[&end] {
+            steps.push(d);
+            end = s;
+        }
+        for d in steps.into_iter().rev() {
+            self.op(d);
+        }
+    }
+
+    // Places the goal tiles of a line running along the border of the
+    // unsolved rectangle, `inward` pointing inside of it. The last two tiles
+    // cannot be placed one after the other : the last one waits in the cell
+    // of the one before, which waits just below, and both slide in at once.
+    fn place_line(&mut self, cells: &[Complex<i32>], inward: Complex<i32>) {
+        let l = cells.len();
+        for &c in cells[..l - 2].iter() {
+            self.move_tile(*self.target.get_cell_ref(c), c);
+            self.set_fixed(c, true);
+        }
+
+        let (p, q) = (cells[l - 2], cells[l - 1]);
+        let (a, b) = (*self.target.get_cell_ref(p), *self.target.get_cell_ref(q));
+        if self.find(a) != p || self.find(b) != q {
+            self.move_tile(b, p);
+            self.set_fixed(p, true);
+            // the blank cannot leave the corner without the tile below
+            if self.grid.zero == q {
+                self.op(inward);
+            }
+            if self.find(a) == q {
+                // stuck in the corner behind the other tile
+                self.move_blank(q + inward, Some(q));
+                self.set_fixed(p, false);
+                self.solve_block(a, b, p, q, inward);
+            } else {
+                self.move_tile(a, p + inward);
+                self.set_fixed(p + inward, true);
+                self.move_blank(q, None);
+                self.set_fixed(p + inward, false);
+                self.op(p + q * -1);
+                self.op(inward);
+            }
+        }
+        self.set_fixed(p, true);
+        self.set_fixed(q, true);
+    }
+}
+
+// the line along one side of the rectangle from (x0, y0) to (x1, y1), and the
+// direction pointing inside of it
+fn side_line(
+    side: usize,
+    (x0, y0, x1, y1): (i32, i32, i32, i32),
+) -> (Vec<Complex<i32>>, Complex<i32>) {
+    match side {
+        0 => (
+            (x0..x1).map(|x| Complex::new(x, y0)).collect(),
+            Complex::new(0, 1),
+        ),
+        1 => (
+            (y0..y1).map(|y| Complex::new(x1 - 1, y)).collect(),
+            Complex::new(-1, 0),
+        ),
+        2 => (
+            (x0..x1).rev().map(|x| Complex::new(x, y1 - 1)).collect(),
+            Complex::new(0, -1),
+        ),
+        _ => (
+            (y0..y1).rev().map(|y| Complex::new(x0, y)).collect(),
+            Complex::new(1, 0),
+        ),
+    }
+}
+
+// Solves the snail goal one line of its outer ring after the other, down to a
+// core of at most 3x3 which is then solved by A* on its own.
+pub(super) fn constructive(grid: &Grid, target: &Grid) -> Res {
+    let cells = grid.v.len();
+    let mut b = Builder {
+        grid: grid.clone(),
+        target,
+        fixed: vec![false; cells],
+        pos: {
+            let mut pos = vec![Complex::new(0, 0); cells];
+            for (i, &t) in grid.v.iter().enumerate() {
+                pos[t as usize] = Complex::new(i as i32 % grid.width, i as i32 / grid.width);
+            }
+            pos
+        },
+        stamp: vec![0; cells],
+        cost: vec![0; cells],
+        from: vec![Complex::new(0, 0); cells],
+        open: BinaryHeap::new(),
+        run: 0,
+        res: Res {
+            time_complexity: 0,
+            size_complexity: cells,
+            sequence: Vec::new(),
+            lower_bound: None,
+            grid: grid.clone(),
+        },
+    };
+
+    // The unsolved rectangle, lines are peeled in the same order as the
+    // snail : top, right, bottom, left. A side is skipped when the rectangle
+    // is too shallow across it for the 2x3 block, too thin along it for the
+    // last two tiles, or when it holds the goal of the blank.
+    let (mut x0, mut y0, mut x1, mut y1) = (0, 0, grid.width, grid.height);
+    let mut side = 0;
+    loop {
+        let (w, h) = (x1 - x0, y1 - y0);
+        let peelable = |s: usize| {
+            let (along, across) = match s % 2 {
+                0 => (w, h),
+                _ => (h, w),
+            };
+            across > 3 && along >= 2 && !side_line(s, (x0, y0, x1, y1)).0.contains(&target.zero)
+        };
+        let Some(s) = (side..side + 4).map(|s| s % 4).find(|&s| peelable(s)) else {
+            break;
+        };
+        side = s;
+        let (line, inward) = side_line(side, (x0, y0, x1, y1));
+        b.place_line(&line, inward);
+        match side {
+            0 => y0 += 1,
+            1 => x1 -= 1,
+            2 => y1 -= 1,
+            _ => x0 += 1,
+        }
+        side = (side + 1) % 4;
+    }
+
+    // the core is solved as a puzzle of its own
+    let (core, goal) = sub_puzzle(&b.grid, target, Complex::new(x0, y0), x1 - x0, y1 - y0);
+    let solved = astar(
+        &core,
+        &goal,
+        Heuristic::LinearConflict,
+        &Stop::NEVER,
+        |g, h| (g + h) as f64,
+    )
+    .unwrap();
+    for &d in solved.sequence.iter() {
+        b.op(d);
+    }
+    b.res.time_complexity += solved.time_complexity;
+    b.res.size_complexity = b.res.size_complexity.max(solved.size_complexity);
+    b.res
+}
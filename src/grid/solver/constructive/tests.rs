@@ -0,0 +1,72 @@
+use super::constructive;
+use crate::grid::{Grid, Layout};
+
+fn check(g: &Grid) {
+    check_to(g, &Grid::create_solved_grid(g.width, g.height));
+}
+
+fn check_to(g: &Grid, target: &Grid) {
+    let res = constructive(g, target);
+    let mut g = g.clone();
+    for &op in res.sequence.iter() {
+        assert!(g.is_op_legal(op));
+        g.op(op);
+    }
+    assert!(g.v == target.v);
+}
+
+#[test]
+fn small() {
+    for size in 1..4 {
+        check(&Grid::create_solved_grid(size, size));
+        for _ in 0..10 {
+            check(&Grid::create_solved_grid(size, size).scramble(1000));
+        }
+    }
+}
+
+#[test]
+fn random() {
+    for size in 4..12 {
+        for _ in 0..20 {
+            check(&Grid::create_solved_grid(size, size).scramble(10_000));
+        }
+    }
+}
+
+#[test]
+fn rectangle() {
+    for (width, height) in [
+        (3, 5),
+        (5, 3),
+        (4, 6),
+        (6, 4),
+        (2, 7),
+        (7, 2),
+        (10, 4),
+        (1, 5),
+    ] {
+        for _ in 0..10 {
+            check(&Grid::create_solved_grid(width, height).scramble(10_000));
+        }
+    }
+}
+
+#[test]
+fn goal_layouts() {
+    for layout in [Layout::RowMajor, Layout::ColumnMajor] {
+        for (width, height) in [(4, 4), (6, 5), (3, 7)] {
+            let goal = Grid::create_goal(width, height, layout);
+            for _ in 0..10 {
+                check_to(&goal.scramble(10_000), &goal);
+            }
+        }
+    }
+    let goal = Grid::create_solved_grid(8, 8).scramble(10_000);
+    check_to(&goal.scramble(10_000), &goal);
+}
+
+#[test]
+fn large() {
+    check(&Grid::create_solved_grid(50, 50).scramble(100_000));
+}
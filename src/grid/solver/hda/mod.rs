@@ -0,0 +1,200 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        atomic::{AtomicI32, AtomicUsize, Ordering},
+        mpsc::{channel, Receiver, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{Hcost, Heuristic, Res, State};
+use crate::{complex::Complex, grid::Grid};
+
+#[cfg(test)]
+mod tests;
+
+type Seen = HashMap<Vec<i32>, (i32, Option<Complex<i32>>)>;
+
+// `work` counts the workers that are not idle plus the states sent but not
+// handled yet. A worker only increases it while it is counted itself, or
+// while the state it received still is, so once it drops to zero nothing can
+// wake a worker up again and the search is over.
+struct Shared<'a> {
+    hcost: &'a Hcost,
+    target: &'a Grid,
+    work: AtomicUsize,
+    best: AtomicI32,
+    time_complexity: AtomicUsize,
+}
+
+struct Worker<'a> {
+    shared: &'a Shared<'a>,
+    senders: Vec<Sender<State>>,
+    receiver: Receiver<State>,
+    id: usize,
+    active: bool,
+    open_set: BinaryHeap<Reverse<State>>,
+    seen: Seen,
+}
+
+fn owner(v: &Vec<i32>, threads: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    v.hash(&mut hasher);
+    (hasher.finish() % threads as u64) as usize
+}
+
+impl Worker<'_> {
+    fn receive(&mut self, s: State) {
+        if self
+            .seen
+            .get(&s.grid.v)
+            .is_some_and(|&(g, _)| g <= s.g_cost)
+        {
+            return;
+        }
+        if s.grid.v == self.shared.target.v {
+            self.shared.best.fetch_min(s.g_cost, Ordering::SeqCst);
+        }
+        self.seen.insert(s.grid.v.clone(), (s.g_cost, s.last_op));
+        self.open_set.push(Reverse(s));
+    }
+
+    fn message(&mut self, s: State) {
+        if !self.active {
+            self.active = true;
+            self.shared.work.fetch_add(1, Ordering::SeqCst);
+        }
+        self.receive(s);
+        self.shared.work.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    // only the states that could still lead to a shorter path than the best
+    // one found so far are worth expanding
+    fn pop(&mut self) -> Option<State> {
+        while let Some(Reverse(s)) = self.open_set.peek() {
+            if s.g_cost + s.h_cost >= self.shared.best.load(Ordering::SeqCst) {
+                return None;
+            }
+            let s = self.open_set.pop().unwrap().0;
+            if self.seen[&s.grid.v].0 == s.g_cost {
+                return Some(s);
+            }
+        }
+        None
+    }
+
+    fn expand(&mut self, s: State) {
+        self.shared.time_complexity.fetch_add(1, Ordering::Relaxed);
+        let dirs = Grid::dirs();
+        let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
+        for op in ops {
+            let mut ns = s.clone();
+            ns.grid.op(*op);
+            ns.g_cost += 1;
+            ns.last_op = Some(*op);
+            self.shared.hcost.smart_hcost(&s, &mut ns);
+            ns.f_cost = (ns.g_cost + ns.h_cost) as f64;
+            match owner(&ns.grid.v, self.senders.len()) {
+                id if id == self.id => self.receive(ns),
+                id => {
+                    self.shared.work.fetch_add(1, Ordering::SeqCst);
+                    self.senders[id].send(ns).unwrap();
+                }
+            }
+        }
+    }
+
+    fn run(mut self) -> Seen {
+        loop {
+            while let Ok(s) = self.receiver.try_recv() {
+                self.message(s);
+            }
+            if let Some(s) = self.pop() {
+                self.expand(s);
+                continue;
+            }
+            if self.active {
+                self.active = false;
+                self.shared.work.fetch_sub(1, Ordering::SeqCst);
+            }
+            if self.shared.work.load(Ordering::SeqCst) == 0 {
+                return self.seen;
+            }
+            if let Ok(s) = self.receiver.recv_timeout(Duration::from_millis(1)) {
+                self.message(s);
+            }
+        }
+    }
+}
+
+// HDA* : every state belongs to the worker its hash points to, which is the
+// only one to keep track of it. The search goes on until no worker holds a
+// state that could improve on the best path found.
+pub(super) fn hda(grid: &Grid, target: &Grid, h: Heuristic, threads: usize) -> Res {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let threads = threads.max(1);
+    let hcost = Hcost::new(target, h);
+    let shared = Shared {
+        hcost: &hcost,
+        target,
+        work: AtomicUsize::new(threads),
+        best: AtomicI32::new(i32::MAX),
+        time_complexity: AtomicUsize::new(0),
+    };
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = (0..threads).map(|_| channel()).unzip();
+    let mut workers = receivers
+        .into_iter()
+        .enumerate()
+        .map(|(id, receiver)| Worker {
+            shared: &shared,
+            senders: senders.clone(),
+            receiver,
+            id,
+            active: true,
+            open_set: BinaryHeap::new(),
+            seen: HashMap::new(),
+        })
+        .collect::<Vec<_>>();
+    let mut state = State {
+        grid: grid.clone(),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    hcost.init_hcost(&mut state);
+    state.f_cost = state.h_cost as f64;
+    workers[owner(&grid.v, threads)].receive(state);
+
+    let seen = thread::scope(|s| {
+        let handles = workers
+            .into_iter()
+            .map(|w| s.spawn(|| w.run()))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    res.time_complexity = shared.time_complexity.into_inner();
+    res.size_complexity = seen.iter().map(|s| s.len()).sum();
+    let mut g = target.clone();
+    while let Some(op) = seen[owner(&g.v, threads)][&g.v].1 {
+        res.sequence.push(op);
+        g.op(op * -1);
+    }
+    res.sequence.reverse();
+    res
+}
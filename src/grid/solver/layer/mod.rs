@@ -0,0 +1,78 @@
+use rand::{seq::IteratorRandom, thread_rng};
+use std::collections::HashSet;
+
+use super::{solve, Algo, Heuristic, SolveError};
+use crate::grid::Grid;
+
+#[cfg(test)]
+mod tests;
+
+// boards up to this size are enumerated breadth first
+const BFS_CELLS: i32 = 10;
+
+// bigger boards give up after this many samples, the distance may be longer
+// than any puzzle needs
+const ATTEMPTS: u32 = 12;
+
+// A puzzle whose optimal solution to `goal` is exactly `distance` moves long,
+// None when no puzzle is that far. Small boards are drawn uniformly from the
+// whole layer, bigger ones are sampled.
+pub fn at_distance(goal: &Grid, distance: usize) -> Result<Option<Grid>, SolveError> {
+    if goal.width == 1 || goal.height == 1 || goal.width * goal.height <= BFS_CELLS {
+        return Ok(layer(goal, distance).into_iter().choose(&mut thread_rng()));
+    }
+    sample(goal, distance)
+}
+
+// every puzzle exactly `distance` moves away from `goal`
+fn layer(goal: &Grid, distance: usize) -> Vec<Grid> {
+    let mut seen = HashSet::from([goal.v.clone()]);
+    let mut layer = vec![goal.clone()];
+    for _ in 0..distance {
+        let mut next = Vec::new();
+        for g in layer.iter() {
+            let dirs = Grid::dirs();
+            let ops = dirs.iter().filter(|d| g.is_op_legal(**d));
+            for op in ops {
+                let mut ng = g.clone();
+                ng.op(*op);
+                if seen.insert(ng.v.clone()) {
+                    next.push(ng);
+                }
+            }
+        }
+        if next.is_empty() {
+            return next;
+        }
+        layer = next;
+    }
+    layer
+}
+
+// Solves longer and longer random walks, then shuffles, until one is at least
+// `distance` moves away, then follows its solution until `distance` moves are
+// left, as the rest of an optimal path is an optimal solution too.
+fn sample(goal: &Grid, distance: usize) -> Result<Option<Grid>, SolveError> {
+    for attempt in 0..ATTEMPTS {
+        let walk = distance.max(1).saturating_mul(2 << attempt);
+        // a longer walk would be no better than a shuffle
+        let mut g = match walk < goal.v.len().pow(2) {
+            true => goal.scramble(walk as i32),
+            false => goal.shuffle(true).unwrap(),
+        };
+        // a quick solution short enough rules the puzzle out without the
+        // optimal search
+        let h = Heuristic::WalkingDistance;
+        if solve(&g, goal, h, Algo::WeightedAstar(2.), None)?.length() < distance {
+            continue;
+        }
+        let res = solve(&g, goal, h, Algo::IDAstar, None)?;
+        if let Some(extra) = res.sequence.len().checked_sub(distance) {
+            for op in res.sequence[..extra].iter() {
+                g.op(*op);
+            }
+            return Ok(Some(g));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,47 @@
+use super::{at_distance, layer};
+use crate::grid::{
+    solver::{solve, Algo, Heuristic},
+    Grid, Layout,
+};
+
+fn check(goal: &Grid, distance: usize) {
+    let g = at_distance(goal, distance).unwrap().unwrap();
+    assert!(g.v[(g.zero.y * g.width + g.zero.x) as usize] == 0);
+    let res = solve(&g, goal, Heuristic::WalkingDistance, Algo::IDAstar, None).unwrap();
+    assert!(res.length() == distance);
+}
+
+#[test]
+fn layers() {
+    // the two hardest 8-puzzles are 31 moves away from the row-major goal
+    let goal = Grid::create_goal(3, 3, Layout::RowMajor);
+    assert!(layer(&goal, 0).len() == 1);
+    assert!(layer(&goal, 1).len() == 2);
+    assert!(layer(&goal, 2).len() == 4);
+    assert!(layer(&goal, 31).len() == 2);
+    assert!(at_distance(&goal, 32).unwrap().is_none());
+    let line = Grid::create_solved_grid(5, 1);
+    assert!(layer(&line, 4).len() == 1);
+    assert!(at_distance(&line, 5).unwrap().is_none());
+}
+
+#[test]
+fn exact_distance() {
+    for distance in [0, 1, 12, 24] {
+        check(&Grid::create_solved_grid(3, 3), distance);
+    }
+    check(&Grid::create_solved_grid(2, 5), 20);
+    check(&Grid::create_solved_grid(1, 6), 3);
+    for distance in [0, 5, 20, 30] {
+        check(&Grid::create_solved_grid(4, 4), distance);
+    }
+    check(&Grid::create_goal(3, 4, Layout::ColumnMajor), 18);
+}
+
+#[test]
+fn out_of_reach() {
+    // no 11-puzzle needs more than 53 moves
+    let goal = Grid::create_solved_grid(3, 4);
+    assert!(at_distance(&goal, 80).unwrap().is_none());
+    assert!(at_distance(&goal, usize::MAX).unwrap().is_none());
+}
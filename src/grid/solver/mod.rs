@@ -3,10 +3,38 @@ use std::{
     collections::{BinaryHeap, HashMap, HashSet},
     error::Error,
     fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-use super::Grid;
+use super::{moves::to_letters, Grid};
 use crate::complex::Complex;
+use ara::ara;
+use beam::beam;
+use bidirectional::bidirectional;
+use constructive::constructive;
+use hda::hda;
+use optimize::shorten;
+use parallel::parallel_idastar;
+use pdb::{Pdb, PdbError};
+use serde_json::{json, Value};
+use smastar::smastar;
+use wd::WalkingDistance;
+
+mod ara;
+mod beam;
+mod bidirectional;
+mod constructive;
+mod hda;
+pub mod layer;
+mod optimize;
+mod parallel;
+pub mod pdb;
+mod smastar;
+mod wd;
 
 #[cfg(test)]
 mod tests;
@@ -16,7 +44,9 @@ pub struct State {
     grid: Grid,
     h_cost: i32,
     g_cost: i32,
+    f_cost: f64,
     last_op: Option<Complex<i32>>,
+    wd: [u32; 2],
 }
 
 impl PartialEq for State {
@@ -35,41 +65,91 @@ impl PartialOrd for State {
 
 impl Ord for State {
     fn cmp(&self, other: &Self) -> Ordering {
-        match (
-            self.h_cost + self.g_cost as i32,
-            other.h_cost + other.g_cost as i32,
-        ) {
-            (c1, c2) if c1 != c2 => c1.cmp(&c2),
-            _ => self.h_cost.cmp(&other.h_cost),
+        match self.f_cost.total_cmp(&other.f_cost) {
+            Ordering::Equal => self.h_cost.cmp(&other.h_cost),
+            o => o,
         }
     }
 }
 
+// past this many cells in total, only the first and the last boards of a
+// solution are printed
+const MAX_PRINTED_CELLS: usize = 1_000_000;
+
 pub struct Res {
     time_complexity: usize,
     size_complexity: usize,
     sequence: Vec<Complex<i32>>,
+    lower_bound: Option<i32>,
     grid: Grid,
 }
 
 impl fmt::Display for Res {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut g = self.grid.clone();
-        write!(f, "-------------------------------\n")?;
+        writeln!(f, "-------------------------------")?;
         write!(f, "sequence :\n\n")?;
         write!(f, "{:?}\n\n", g)?;
-        for i in self.sequence.clone() {
-            g.op(i);
+        let boards = self.sequence.len() + 1;
+        if boards * g.v.len() <= MAX_PRINTED_CELLS {
+            for &i in self.sequence.iter() {
+                g.op(i);
+                write!(f, "{:?}\n\n", g)?;
+            }
+        } else if boards > 1 {
+            self.sequence.iter().for_each(|&i| g.op(i));
+            write!(f, "... {} boards left out ...\n\n", boards - 2)?;
             write!(f, "{:?}\n\n", g)?;
         }
-        write!(f, "complexity in time : {:?}\n", self.time_complexity)?;
-        write!(f, "complexity in size : {:?}\n", self.size_complexity)?;
-        write!(
-            f,
-            "total number of operations : {:?}\n",
-            self.sequence.len()
-        )?;
-        write!(f, "-------------------------------\n")
+        writeln!(f, "complexity in time : {:?}", self.time_complexity)?;
+        writeln!(f, "complexity in size : {:?}", self.size_complexity)?;
+        writeln!(f, "total number of operations : {:?}", self.sequence.len())?;
+        writeln!(f, "moves : {}", to_letters(&self.sequence))?;
+        if let Some(lower_bound) = self.lower_bound {
+            let gap = self.sequence.len() as i32 - lower_bound;
+            writeln!(f, "lower bound : {} ({} above)", lower_bound, gap)?;
+        }
+        writeln!(f, "-------------------------------")
+    }
+}
+
+impl Res {
+    pub fn length(&self) -> usize {
+        self.sequence.len()
+    }
+
+    pub fn time_complexity(&self) -> usize {
+        self.time_complexity
+    }
+
+    pub fn size_complexity(&self) -> usize {
+        self.size_complexity
+    }
+
+    pub fn to_json(&self, algo: &str, heuristic: &str, wall_time: Duration) -> Value {
+        let mut g = self.grid.clone();
+        let mut tiles = Vec::new();
+        for d in &self.sequence {
+            tiles.push(*g.get_cell_ref(g.zero + *d));
+            g.op(*d);
+        }
+        // the moves end on the goal
+        let directions = self.sequence.iter().map(|d| Grid::letter(*d).to_string());
+        json!({
+            "algorithm": algo,
+            "heuristic": heuristic,
+            "input": self.grid.rows(),
+            "goal": g.rows(),
+            "moves": {
+                "tiles": tiles,
+                "directions": directions.collect::<Vec<_>>(),
+            },
+            "solution_length": self.sequence.len(),
+            "time_complexity": self.time_complexity,
+            "size_complexity": self.size_complexity,
+            "lower_bound": self.lower_bound,
+            "wall_time_ms": wall_time.as_secs_f64() * 1000.,
+        })
     }
 }
 
@@ -78,18 +158,22 @@ pub enum Heuristic {
     Manhattan,
     Euclidian,
     Misplaced,
+    LinearConflict,
+    PatternDatabase,
+    WalkingDistance,
     Zero,
 }
 
 impl Heuristic {
     fn dist(&self, z0: Complex<i32>, z1: Complex<i32>) -> i32 {
         match self {
-            Self::Manhattan => (z0.x - z1.x).abs() + (z0.y - z1.y).abs(),
+            Self::Manhattan | Self::LinearConflict => (z0.x - z1.x).abs() + (z0.y - z1.y).abs(),
             Self::Euclidian => (((z0.x - z1.x).pow(2) + (z0.y - z1.y).pow(2)) as f64)
                 .sqrt()
                 .floor() as i32,
             Self::Misplaced => (z0 != z1) as i32,
             Self::Zero => 0,
+            Self::PatternDatabase | Self::WalkingDistance => unreachable!(),
         }
     }
 }
@@ -98,134 +182,525 @@ impl Heuristic {
 struct Hcost {
     target_m: HashMap<i32, Complex<i32>>,
     h: Heuristic,
+    pdb: Option<Arc<Pdb>>,
+    wd: Option<Arc<WalkingDistance>>,
 }
 
 impl Hcost {
-    fn new(size: i32, h: Heuristic) -> Self {
+    fn new(target: &Grid, h: Heuristic) -> Self {
+        let width = target.width;
         Self {
-            target_m: Grid::create_solved_grid(size)
+            // `solve` has checked that the board fits in a database
+            pdb: matches!(h, Heuristic::PatternDatabase).then(|| Pdb::get(target).unwrap()),
+            wd: matches!(h, Heuristic::WalkingDistance)
+                .then(|| Arc::new(WalkingDistance::new(target))),
+            target_m: target
                 .v
                 .iter()
                 .enumerate()
                 .map(|(i, &v)| (i as i32, v))
-                .map(|(i, v)| (v, Complex::new(i % size, i / size)))
+                .map(|(i, v)| (v, Complex::new(i % width, i / width)))
                 .collect(),
             h,
         }
     }
 
-    fn smart_hcost(&self, state: &State, d: Complex<i32>) -> i32 {
+    // Two extra moves for every tile that has to leave the line to let the
+    // others pass : the tiles that can stay are a longest increasing
+    // subsequence of their goal positions, so the count stays admissible
+    // even when more than two tiles are reversed.
+    fn line_conflicts(&self, grid: &Grid, start: Complex<i32>, step: Complex<i32>) -> i32 {
+        let mut goals = Vec::new();
+        let mut p = start;
+        while grid.contains(p) {
+            let n = *grid.get_cell_ref(p);
+            if n != 0 {
+                let t = self.target_m[&n];
+                if step.x == 0 && t.x == p.x {
+                    goals.push(t.y);
+                } else if step.y == 0 && t.y == p.y {
+                    goals.push(t.x);
+                }
+            }
+            p += step;
+        }
+        let mut lis: Vec<i32> = Vec::new();
+        for g in goals.iter() {
+            match lis.binary_search(g) {
+                Ok(_) => {}
+                Err(i) if i == lis.len() => lis.push(*g),
+                Err(i) => lis[i] = *g,
+            }
+        }
+        2 * (goals.len() - lis.len()) as i32
+    }
+
+    fn row_conflicts(&self, grid: &Grid, y: i32) -> i32 {
+        self.line_conflicts(grid, Complex::new(0, y), Complex::new(1, 0))
+    }
+
+    fn col_conflicts(&self, grid: &Grid, x: i32) -> i32 {
+        self.line_conflicts(grid, Complex::new(x, 0), Complex::new(0, 1))
+    }
+
+    fn init_hcost(&self, state: &mut State) {
+        if let Some(wd) = &self.wd {
+            state.wd = wd.keys(&state.grid);
+        }
+        state.h_cost = self.hcost(&state.grid);
+    }
+
+    fn smart_hcost(&self, state: &State, next: &mut State) {
+        let d = next.last_op.unwrap();
+        let (old_p, new_p) = (state.grid.zero + d, state.grid.zero);
+        if let Some(pdb) = &self.pdb {
+            next.h_cost = pdb.smart_hcost(state.h_cost, &state.grid, old_p, new_p);
+            return;
+        }
+        if let Some(wd) = &self.wd {
+            next.wd = wd.step(state.wd, &state.grid, d);
+            next.h_cost = wd.cost(next.wd);
+            return;
+        }
+        let t = self.target_m[state.grid.get_cell_ref(old_p)];
         let mut c = state.h_cost;
-        c -= self.h.dist(
-            state.grid.zero + d,
-            self.target_m[state.grid.get_cell_ref(state.grid.zero + d)],
-        );
-        c += self.h.dist(
-            state.grid.zero,
-            self.target_m[state.grid.get_cell_ref(state.grid.zero + d)],
-        );
-        c
+        c -= self.h.dist(old_p, t);
+        c += self.h.dist(new_p, t);
+        if let Heuristic::LinearConflict = self.h {
+            // the moved tile keeps its order in the line it slides along, only
+            // the two lines it leaves and enters across can change
+            if d.y == 0 {
+                for x in [old_p.x, new_p.x] {
+                    c -= self.col_conflicts(&state.grid, x);
+                    c += self.col_conflicts(&next.grid, x);
+                }
+            } else {
+                for y in [old_p.y, new_p.y] {
+                    c -= self.row_conflicts(&state.grid, y);
+                    c += self.row_conflicts(&next.grid, y);
+                }
+            }
+        }
+        next.h_cost = c;
     }
 
     fn hcost(&self, grid: &Grid) -> i32 {
+        if let Some(pdb) = &self.pdb {
+            return pdb.hcost(grid);
+        }
+        if let Some(wd) = &self.wd {
+            return wd.cost(wd.keys(grid));
+        }
         let mut c = 0;
-        for y in 0..grid.size {
-            for x in 0..grid.size {
+        for y in 0..grid.height {
+            for x in 0..grid.width {
                 let p = Complex::new(x, y);
                 if *grid.get_cell_ref(p) != 0 {
                     c += self.h.dist(p, self.target_m[grid.get_cell_ref(p)]);
                 }
             }
         }
+        if let Heuristic::LinearConflict = self.h {
+            for y in 0..grid.height {
+                c += self.row_conflicts(grid, y);
+            }
+            for x in 0..grid.width {
+                c += self.col_conflicts(grid, x);
+            }
+        }
         c
     }
 }
 
-fn unroll(grid: &Grid) -> Vec<i32> {
-    let mut v = vec![0; grid.size.pow(2) as usize];
-    let solved = Grid::create_solved_grid(grid.size);
+// Why a grid cannot reach its goal.
+pub enum Unsolvable {
+    Dimensions((i32, i32), (i32, i32)),
+    // the first cell of a single line where the tiles are out of order
+    Line(usize),
+    Parity {
+        inversions: usize,
+        distance: usize,
+        row: usize,
+        goal_row: usize,
+    },
+}
 
-    for (i, n) in grid.v.iter().enumerate() {
-        v[((solved.v[i] - 1).rem_euclid(grid.size.pow(2))) as usize] = *n;
+fn parity(n: usize) -> &'static str {
+    match n.is_multiple_of(2) {
+        true => "even",
+        false => "odd",
     }
-    v
 }
 
-fn is_solvable(grid: &Grid) -> bool {
-    let mut inversions = 0;
-    let g = unroll(grid);
-
-    for (i, n1) in g.iter().enumerate() {
-        for n2 in g.iter().skip(i + 1) {
-            if n1 > n2 && *n2 != 0 {
-                inversions += 1;
+impl fmt::Display for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unsolvable::Dimensions((w, h), (gw, gh)) => {
+                write!(f, "the puzzle is {}x{} but the goal is {}x{}", w, h, gw, gh)
             }
+            Unsolvable::Line(i) => write!(
+                f,
+                "the tiles of a single line cannot pass each other, cell {} differs from the goal",
+                i + 1
+            ),
+            Unsolvable::Parity {
+                inversions,
+                distance,
+                row,
+                goal_row,
+            } => {
+                write!(
+                    f,
+                    "{} inversions ({}) against the goal but the blank is at distance {} from its goal cell ({}), ",
+                    inversions,
+                    parity(*inversions),
+                    distance,
+                    parity(*distance),
+                )?;
+                match row == goal_row {
+                    true => write!(f, "on its goal row")?,
+                    false => write!(
+                        f,
+                        "on row {} instead of row {} ({} row offset)",
+                        row + 1,
+                        goal_row + 1,
+                        parity(row.abs_diff(*goal_row))
+                    )?,
+                }
+                write!(f, ", and every move flips both parities")
+            }
+        }
+    }
+}
+
+impl Error for Unsolvable {}
+
+impl fmt::Debug for Unsolvable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", *self)
+    }
+}
+
+// counts the pairs out of order with a Fenwick tree
+fn inversions(p: &[usize]) -> usize {
+    let mut tree = vec![0; p.len() + 1];
+    let mut count = 0;
+    for (seen, &x) in p.iter().enumerate() {
+        let mut i = x + 1;
+        let mut below = 0;
+        while i > 0 {
+            below += tree[i];
+            i &= i - 1;
+        }
+        count += seen - below;
+        let mut i = x + 1;
+        while i < tree.len() {
+            tree[i] += 1;
+            i += i & i.wrapping_neg();
         }
     }
-    inversions % 2 == 0
+    count
 }
 
-pub struct UnsolvableError;
+// Every move swaps the blank with a tile, flipping both the parity of the
+// permutation that leads to the goal and the parity of the distance of the
+// blank to its goal cell, so the two have to agree. Unlike counting
+// inversions in reading order, this holds whatever the width and the goal.
+pub fn solvability(grid: &Grid, target: &Grid) -> Result<(), Unsolvable> {
+    if (grid.width, grid.height) != (target.width, target.height) {
+        return Err(Unsolvable::Dimensions(
+            (grid.width, grid.height),
+            (target.width, target.height),
+        ));
+    }
+    if grid.width == 1 || grid.height == 1 {
+        // the tiles of a single line cannot pass each other
+        let tiles = |g: &Grid| {
+            g.v.iter()
+                .enumerate()
+                .filter(|(_, &n)| n != 0)
+                .map(|(i, &n)| (i, n))
+                .collect::<Vec<_>>()
+        };
+        return match tiles(grid)
+            .into_iter()
+            .zip(tiles(target))
+            .find(|((_, a), (_, b))| a != b)
+        {
+            Some(((i, _), _)) => Err(Unsolvable::Line(i)),
+            None => Ok(()),
+        };
+    }
+    let mut goal_cell = vec![0; target.v.len()];
+    for (i, &n) in target.v.iter().enumerate() {
+        goal_cell[n as usize] = i;
+    }
+    let p = grid
+        .v
+        .iter()
+        .map(|&n| goal_cell[n as usize])
+        .collect::<Vec<_>>();
+    let w = grid.width as usize;
+    let (z, t) = (grid.v.iter().position(|&n| n == 0).unwrap(), goal_cell[0]);
+    let distance = (z % w).abs_diff(t % w) + (z / w).abs_diff(t / w);
+    let inversions = inversions(&p);
+    match (inversions + distance).is_multiple_of(2) {
+        true => Ok(()),
+        false => Err(Unsolvable::Parity {
+            inversions,
+            distance,
+            row: z / w,
+            goal_row: t / w,
+        }),
+    }
+}
 
-impl fmt::Display for UnsolvableError {
+// how far a search went before it was stopped, the f bound is a lower bound
+// on the length of the solution when the search has one
+pub struct Partial {
+    f_bound: Option<i32>,
+    time_complexity: usize,
+    size_complexity: usize,
+}
+
+impl fmt::Display for Partial {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "unsolvable")
+        if let Some(f_bound) = self.f_bound {
+            write!(f, "f bound : {}, ", f_bound)?;
+        }
+        write!(
+            f,
+            "complexity in time : {}, complexity in size : {}",
+            self.time_complexity, self.size_complexity
+        )
     }
 }
 
-impl Error for UnsolvableError {}
+pub enum SolveError {
+    Unsolvable(Unsolvable),
+    Pdb(PdbError),
+    OutOfMemory(usize),
+    BeamExhausted(usize),
+    TimedOut(Partial),
+    Interrupted(Partial),
+}
 
-impl fmt::Debug for UnsolvableError {
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unsolvable(why) => write!(f, "unsolvable : {}", why),
+            SolveError::Pdb(e) => write!(f, "{}", e),
+            SolveError::OutOfMemory(n) => write!(f, "no solution fits in {} states", n),
+            SolveError::BeamExhausted(n) => write!(f, "the beam of width {} ran out of states", n),
+            SolveError::TimedOut(p) => write!(f, "timed out ({})", p),
+            SolveError::Interrupted(p) => write!(f, "interrupted ({})", p),
+        }
+    }
+}
+
+impl SolveError {
+    // weighted A* and greedy search expand nodes whose g + h goes past the
+    // optimum, so the largest one seen bounds nothing
+    fn unbounded(self) -> Self {
+        match self {
+            SolveError::TimedOut(p) => SolveError::TimedOut(Partial { f_bound: None, ..p }),
+            SolveError::Interrupted(p) => SolveError::Interrupted(Partial { f_bound: None, ..p }),
+            e => e,
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+impl fmt::Debug for SolveError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", *self)
     }
 }
 
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// asks every running search that can be stopped to stop
+pub fn interrupt() {
+    INTERRUPTED.store(true, AtomicOrdering::Relaxed);
+}
+
+// Tells a search when to give up : at a deadline, or after an interruption if
+// it listens to them.
+struct Stop {
+    deadline: Option<Instant>,
+    interruptible: bool,
+}
+
+impl Stop {
+    const NEVER: Stop = Stop {
+        deadline: None,
+        interruptible: false,
+    };
+
+    // looking at the clock on every state would slow searches down
+    const PERIOD: usize = 1024;
+
+    fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            deadline: timeout.map(|t| Instant::now() + t),
+            interruptible: true,
+        }
+    }
+
+    fn stopped(&self) -> Option<fn(Partial) -> SolveError> {
+        if self.interruptible && INTERRUPTED.load(AtomicOrdering::Relaxed) {
+            return Some(SolveError::Interrupted);
+        }
+        match self.deadline {
+            Some(d) if Instant::now() >= d => Some(SolveError::TimedOut),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Algo {
     Astar,
     IDAstar,
+    WeightedAstar(f64),
+    Greedy,
+    UniformCost,
+    Bidirectional,
+    SmaStar(usize),
+    Rbfs,
+    ParallelIDAstar(usize),
+    HDAstar(usize),
+    Beam(usize),
+    Constructive,
 }
 
-pub fn solve(grid: &Grid, h: Heuristic, algo: Algo) -> Result<Res, UnsolvableError> {
-    if !is_solvable(grid) {
-        return Err(UnsolvableError);
+impl Algo {
+    // the only searches that give up at the timeout or when interrupted
+    pub fn stoppable(&self) -> bool {
+        matches!(
+            self,
+            Self::Astar | Self::IDAstar | Self::WeightedAstar(_) | Self::Greedy
+        )
     }
+}
+
+// A*, IDA* and their variants give up at the timeout or when interrupted.
+pub fn solve(
+    grid: &Grid,
+    target: &Grid,
+    h: Heuristic,
+    algo: Algo,
+    timeout: Option<Duration>,
+) -> Result<Res, SolveError> {
+    solvability(grid, target).map_err(SolveError::Unsolvable)?;
+    if let Heuristic::PatternDatabase = h {
+        Pdb::check(target).map_err(SolveError::Pdb)?;
+        // the backward side is guided toward the start, whose database would
+        // have to be built for this puzzle alone
+        if let Algo::Bidirectional = algo {
+            return Err(SolveError::Pdb(PdbError::Bidirectional));
+        }
+    }
+    let stop = Stop::new(timeout);
     Ok(match algo {
-        Algo::Astar => astar(grid, h),
-        Algo::IDAstar => idastar(grid, h),
+        Algo::Astar => astar(grid, target, h, &stop, |g, h| (g + h) as f64)?,
+        Algo::IDAstar => idastar(grid, target, h, &stop)?,
+        Algo::WeightedAstar(w) => astar(grid, target, h, &stop, |g, h| g as f64 + w * h as f64)
+            .map_err(SolveError::unbounded)?,
+        Algo::Greedy => {
+            astar(grid, target, h, &stop, |_, h| h as f64).map_err(SolveError::unbounded)?
+        }
+        Algo::UniformCost => uniform_cost(grid, target),
+        Algo::Bidirectional => bidirectional(grid, target, h),
+        Algo::SmaStar(max_states) => smastar(grid, target, h, max_states)?,
+        Algo::Rbfs => rbfs(grid, target, h),
+        Algo::ParallelIDAstar(threads) => parallel_idastar(grid, target, h, threads),
+        Algo::HDAstar(threads) => hda(grid, target, h, threads),
+        Algo::Constructive => constructive(grid, target),
+        Algo::Beam(width) => {
+            beam(grid, target, h, width).ok_or(SolveError::BeamExhausted(width))?
+        }
     })
 }
 
-fn astar(grid: &Grid, h: Heuristic) -> Res {
+// Reports every solution found along with its suboptimality bound, starting
+// from the given weight, and returns the last one.
+pub fn solve_anytime(
+    grid: &Grid,
+    target: &Grid,
+    h: Heuristic,
+    weight: f64,
+    timeout: Option<Duration>,
+    report: impl FnMut(&Res, f64),
+) -> Result<Res, SolveError> {
+    solvability(grid, target).map_err(SolveError::Unsolvable)?;
+    if let Heuristic::PatternDatabase = h {
+        Pdb::check(target).map_err(SolveError::Pdb)?;
+    }
+    Ok(ara(grid, target, h, weight, &Stop::new(timeout), report))
+}
+
+// Shortens the solution with windows of `k` moves, returns how many moves
+// were removed.
+pub fn optimize(res: &mut Res, k: usize) -> usize {
+    let before = res.sequence.len();
+    res.sequence = shorten(&res.grid, &res.sequence, k);
+    before - res.sequence.len()
+}
+
+fn astar(
+    grid: &Grid,
+    target: &Grid,
+    h: Heuristic,
+    stop: &Stop,
+    priority: impl Fn(i32, i32) -> f64,
+) -> Result<Res, SolveError> {
     let mut res = Res {
         time_complexity: 0,
         size_complexity: 0,
         sequence: Vec::new(),
+        lower_bound: None,
         grid: grid.clone(),
     };
     let mut open_set: BinaryHeap<Reverse<State>> = BinaryHeap::new();
     let mut open_g: HashMap<Vec<i32>, i32> = HashMap::new();
     let mut closed_set: HashMap<Vec<i32>, State> = HashMap::new();
-    let hcost = Hcost::new(grid.size, h);
+    let hcost = Hcost::new(target, h);
     {
-        let state = State {
+        let mut state = State {
             grid: grid.clone(),
             h_cost: 0,
             g_cost: 0,
+            f_cost: 0.,
             last_op: None,
+            wd: [0; 2],
         };
+        hcost.init_hcost(&mut state);
+        state.f_cost = priority(state.g_cost, state.h_cost);
         open_set.push(Reverse(state));
         open_g.insert(grid.v.clone(), 0);
     }
-    open_set.peek_mut().unwrap().0.h_cost = hcost.hcost(&open_set.peek().unwrap().0.grid);
 
-    let target = Grid::create_solved_grid(grid.size);
+    let mut f_bound = 0;
     while !closed_set.contains_key(&target.v) {
         let s = open_set.pop().unwrap().0;
+        if closed_set.contains_key(&s.grid.v) {
+            continue;
+        }
         open_g.remove(&s.grid.v);
         res.time_complexity += 1;
         res.size_complexity = res.size_complexity.max(open_set.len() + closed_set.len());
+        f_bound = f_bound.max(s.g_cost + s.h_cost);
+        if res.time_complexity.is_multiple_of(Stop::PERIOD) {
+            if let Some(stopped) = stop.stopped() {
+                return Err(stopped(Partial {
+                    f_bound: Some(f_bound),
+                    time_complexity: res.time_complexity,
+                    size_complexity: res.size_complexity,
+                }));
+            }
+        }
         let dirs = Grid::dirs();
         let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
         for op in ops {
@@ -236,7 +711,8 @@ fn astar(grid: &Grid, h: Heuristic) -> Res {
             }
             ns.g_cost += 1;
             ns.last_op = Some(*op);
-            ns.h_cost = hcost.smart_hcost(&s, *op);
+            hcost.smart_hcost(&s, &mut ns);
+            ns.f_cost = priority(ns.g_cost, ns.h_cost);
             if open_g.contains_key(&ns.grid.v) && open_g[&ns.grid.v] < ns.g_cost {
                 continue;
             }
@@ -251,74 +727,274 @@ fn astar(grid: &Grid, h: Heuristic) -> Res {
         g.op(op * -1);
     }
     res.sequence.reverse();
+    Ok(res)
+}
+
+// The rectangle of cells at `origin` as a puzzle of its own, in `grid` and in
+// `target`, its tiles renumbered in the order of the cells they hold in
+// `target`. Both have to hold the same tiles there, the blank among them.
+fn sub_puzzle(
+    grid: &Grid,
+    target: &Grid,
+    origin: Complex<i32>,
+    width: i32,
+    height: i32,
+) -> (Grid, Grid) {
+    let cells = (0..height)
+        .flat_map(|y| (0..width).map(move |x| origin + Complex::new(x, y)))
+        .collect::<Vec<_>>();
+    let rank = cells
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| (*target.get_cell_ref(c), i as i32 + 1))
+        .chain([(0, 0)])
+        .collect::<HashMap<_, _>>();
+    let sub = |g: &Grid| Grid {
+        v: cells.iter().map(|&c| rank[g.get_cell_ref(c)]).collect(),
+        width,
+        height,
+        zero: g.zero + origin * -1,
+    };
+    (sub(grid), sub(target))
+}
+
+// Dijkstra without any heuristic, ties are broken by generation order so the
+// frontier is expanded layer by layer like a breadth-first search.
+fn uniform_cost(grid: &Grid, target: &Grid) -> Res {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let mut open_set: BinaryHeap<Reverse<(i32, usize)>> = BinaryHeap::new();
+    let mut nodes: Vec<Option<(Grid, Option<Complex<i32>>)>> = Vec::new();
+    let mut open_g: HashMap<Vec<i32>, i32> = HashMap::new();
+    let mut closed_set: HashMap<Vec<i32>, Option<Complex<i32>>> = HashMap::new();
+    open_set.push(Reverse((0, 0)));
+    nodes.push(Some((grid.clone(), None)));
+    open_g.insert(grid.v.clone(), 0);
+
+    while !closed_set.contains_key(&target.v) {
+        let Reverse((g_cost, id)) = open_set.pop().unwrap();
+        let (g, last_op) = nodes[id].take().unwrap();
+        if closed_set.contains_key(&g.v) {
+            continue;
+        }
+        open_g.remove(&g.v);
+        res.time_complexity += 1;
+        res.size_complexity = res.size_complexity.max(open_set.len() + closed_set.len());
+        let dirs = Grid::dirs();
+        let ops = dirs.iter().filter(|d| g.is_op_legal(**d));
+        for op in ops {
+            let mut ng = g.clone();
+            ng.op(*op);
+            if closed_set.contains_key(&ng.v) || open_g.get(&ng.v).is_some_and(|&c| c <= g_cost + 1)
+            {
+                continue;
+            }
+            open_g.insert(ng.v.clone(), g_cost + 1);
+            open_set.push(Reverse((g_cost + 1, nodes.len())));
+            nodes.push(Some((ng, Some(*op))));
+        }
+        closed_set.insert(g.v, last_op);
+    }
+    let mut g = target.clone();
+    while let Some(op) = closed_set[&g.v] {
+        res.sequence.push(op);
+        g.op(op * -1);
+    }
+    res.sequence.reverse();
     res
 }
 
-fn idastar(grid: &Grid, h: Heuristic) -> Res {
-    struct Env {
+fn idastar(grid: &Grid, target: &Grid, h: Heuristic, stop: &Stop) -> Result<Res, SolveError> {
+    struct Env<'a> {
         hcost: Hcost,
         target: Grid,
         lim: i32,
         seen: HashSet<Vec<i32>>,
         res: Res,
+        stop: &'a Stop,
     }
 
-    let hcost = Hcost::new(grid.size, h);
+    let hcost = Hcost::new(target, h);
+    let mut state = State {
+        grid: grid.clone(),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    hcost.init_hcost(&mut state);
     let mut env = Env {
-        target: Grid::create_solved_grid(grid.size),
-        lim: hcost.hcost(grid),
+        target: target.clone(),
+        lim: state.h_cost,
         hcost,
         seen: HashSet::new(),
         res: Res {
             size_complexity: 0,
             time_complexity: 0,
             sequence: Vec::new(),
+            lower_bound: None,
             grid: grid.clone(),
         },
+        stop,
     };
     env.seen.insert(grid.v.clone());
 
     enum Output {
         Found,
         Limit(Option<i32>),
+        Stopped(fn(Partial) -> SolveError),
     }
 
-    fn compute(env: &mut Env, grid: &Grid, g: i32) -> Output {
-        let f = g + env.hcost.hcost(grid);
+    fn compute(env: &mut Env, s: &State) -> Output {
+        let f = s.g_cost + s.h_cost;
         if f > env.lim {
             return Output::Limit(Some(f));
         }
 
-        if grid.v == env.target.v {
+        if s.grid.v == env.target.v {
             return Output::Found;
         }
 
         let mut min_lim: Option<i32> = None;
         let dirs = Grid::dirs();
-        let ops = dirs.iter().filter(|d| grid.is_op_legal(**d));
+        let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
         for op in ops {
             env.res.time_complexity += 1;
-            let mut ngrid = grid.clone();
-            ngrid.op(*op);
-            if env.seen.contains(&ngrid.v) {
+            if env.res.time_complexity.is_multiple_of(Stop::PERIOD) {
+                if let Some(stopped) = env.stop.stopped() {
+                    return Output::Stopped(stopped);
+                }
+            }
+            let mut ns = s.clone();
+            ns.grid.op(*op);
+            if env.seen.contains(&ns.grid.v) {
                 continue;
             }
+            ns.g_cost += 1;
+            ns.last_op = Some(*op);
+            env.hcost.smart_hcost(s, &mut ns);
             env.res.sequence.push(*op);
-            env.seen.insert(ngrid.v.clone());
+            env.seen.insert(ns.grid.v.clone());
             env.res.size_complexity = env.res.size_complexity.max(env.res.sequence.len());
-            match compute(env, &ngrid, g + 1) {
+            match compute(env, &ns) {
                 Output::Limit(Some(lim)) => min_lim = Some(min_lim.unwrap_or(lim).min(lim)),
                 Output::Limit(None) => {}
-                Output::Found => return Output::Found,
+                o => return o,
             }
             env.res.sequence.pop();
-            env.seen.remove(&ngrid.v);
+            env.seen.remove(&ns.grid.v);
         }
         Output::Limit(min_lim)
     }
 
-    while let Output::Limit(lim) = compute(&mut env, grid, 0) {
-        env.lim = lim.unwrap();
+    loop {
+        match compute(&mut env, &state) {
+            Output::Found => return Ok(env.res),
+            Output::Limit(lim) => env.lim = lim.unwrap(),
+            // no solution is shorter than the threshold being searched
+            Output::Stopped(stopped) => {
+                return Err(stopped(Partial {
+                    f_bound: Some(env.lim),
+                    time_complexity: env.res.time_complexity,
+                    size_complexity: env.res.size_complexity,
+                }))
+            }
+        }
+    }
+}
+
+fn rbfs(grid: &Grid, target: &Grid, h: Heuristic) -> Res {
+    struct Env {
+        hcost: Hcost,
+        target: Grid,
+        seen: HashSet<Vec<i32>>,
+        res: Res,
+    }
+
+    let mut env = Env {
+        hcost: Hcost::new(target, h),
+        target: target.clone(),
+        seen: HashSet::new(),
+        res: Res {
+            size_complexity: 0,
+            time_complexity: 0,
+            sequence: Vec::new(),
+            lower_bound: None,
+            grid: grid.clone(),
+        },
+    };
+    env.seen.insert(grid.v.clone());
+
+    enum Output {
+        Found,
+        Limit(i32),
+    }
+
+    // `f` is the backed-up f cost of `s`, which can be higher than g + h
+    // when the subtree was already explored and forgotten. Children inherit
+    // it, then the best one is explored for as long as it stays below both
+    // `lim` and the f cost of its best sibling.
+    fn compute(env: &mut Env, s: &State, f: i32, lim: i32) -> Output {
+        if s.grid.v == env.target.v {
+            return Output::Found;
+        }
+
+        let mut children = Vec::new();
+        let dirs = Grid::dirs();
+        let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
+        for op in ops {
+            env.res.time_complexity += 1;
+            let mut ns = s.clone();
+            ns.grid.op(*op);
+            if env.seen.contains(&ns.grid.v) {
+                continue;
+            }
+            ns.g_cost += 1;
+            ns.last_op = Some(*op);
+            env.hcost.smart_hcost(s, &mut ns);
+            let nf = (ns.g_cost + ns.h_cost).max(f);
+            children.push((nf, ns));
+        }
+
+        loop {
+            children.sort_by_key(|c| c.0);
+            let Some((best, ns)) = children.first() else {
+                return Output::Limit(i32::MAX);
+            };
+            if *best > lim {
+                return Output::Limit(*best);
+            }
+            let alt = children.get(1).map_or(i32::MAX, |c| c.0);
+            let ns = ns.clone();
+
+            env.res.sequence.push(ns.last_op.unwrap());
+            env.seen.insert(ns.grid.v.clone());
+            env.res.size_complexity = env.res.size_complexity.max(env.res.sequence.len());
+            match compute(env, &ns, children[0].0, lim.min(alt)) {
+                Output::Found => return Output::Found,
+                Output::Limit(nf) => children[0].0 = nf,
+            }
+            env.res.sequence.pop();
+            env.seen.remove(&ns.grid.v);
+        }
     }
+
+    let mut state = State {
+        grid: grid.clone(),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    env.hcost.init_hcost(&mut state);
+    let f = state.h_cost;
+    compute(&mut env, &state, f, i32::MAX);
     env.res
 }
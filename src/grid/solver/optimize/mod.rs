@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use rand::{thread_rng, Rng};
+
+use super::{astar, sub_puzzle, Heuristic, Stop};
+use crate::{complex::Complex, grid::Grid};
+
+#[cfg(test)]
+mod tests;
+
+// A grid is hashed as the sum of its tiles weighted by their cell, which a
+// move updates in constant time.
+struct Hasher {
+    weights: Vec<u64>,
+}
+
+impl Hasher {
+    fn new(cells: usize) -> Self {
+        let mut rng = thread_rng();
+        Self {
+            weights: (0..cells).map(|_| rng.gen()).collect(),
+        }
+    }
+
+    fn hash(&self, grid: &Grid) -> u64 {
+        grid.v
+            .iter()
+            .zip(self.weights.iter())
+            .fold(0, |h, (&t, &w)| h.wrapping_add((t as u64).wrapping_mul(w)))
+    }
+
+    // `grid` is taken before the blank moves along `d`
+    fn step(&self, h: u64, grid: &Grid, d: Complex<i32>) -> u64 {
+        let index = |p: Complex<i32>| (p.y * grid.width + p.x) as usize;
+        let tile = *grid.get_cell_ref(grid.zero + d) as u64;
+        let from = self.weights[index(grid.zero + d)];
+        let to = self.weights[index(grid.zero)];
+        h.wrapping_sub(tile.wrapping_mul(from))
+            .wrapping_add(tile.wrapping_mul(to))
+    }
+}
+
+// Cuts the sequence back every time it comes back to a state it went
+// through, an inverse pair being the shortest of these loops.
+fn splice_loops(grid: &Grid, sequence: &[Complex<i32>], hasher: &Hasher) -> Vec<Complex<i32>> {
+    let mut g = grid.clone();
+    let mut h = hasher.hash(&g);
+    let mut seen = HashMap::from([(h, 0)]);
+    let mut hashes = vec![h];
+    let mut out = Vec::new();
+    for &d in sequence {
+        h = hasher.step(h, &g, d);
+        g.op(d);
+        match seen.get(&h) {
+            Some(&i) => {
+                for x in hashes.drain(i + 1..) {
+                    seen.remove(&x);
+                }
+                out.truncate(i);
+            }
+            None => {
+                out.push(d);
+                hashes.push(h);
+                seen.insert(h, out.len());
+            }
+        }
+    }
+    out
+}
+
+// The tiles moved by a window all lie on the path of the blank, so the
+// window can be solved optimally as a smaller puzzle made of the square
+// around that path, its tiles renumbered after the cells they end up in.
+fn solve_window(grid: &Grid, window: &[Complex<i32>]) -> Option<Vec<Complex<i32>>> {
+    let mut end = grid.clone();
+    let (mut lo, mut hi) = (grid.zero, grid.zero);
+    for &d in window {
+        end.op(d);
+        lo = Complex::new(lo.x.min(end.zero.x), lo.y.min(end.zero.y));
+        hi = Complex::new(hi.x.max(end.zero.x), hi.y.max(end.zero.y));
+    }
+    let size = (hi.x - lo.x).max(hi.y - lo.y) + 3;
+    let (width, height) = (size.min(grid.width), size.min(grid.height));
+    let origin = Complex::new(
+        (lo.x - 1).clamp(0, grid.width - width),
+        (lo.y - 1).clamp(0, grid.height - height),
+    );
+
+    let (start, goal) = sub_puzzle(grid, &end, origin, width, height);
+    let res = astar(
+        &start,
+        &goal,
+        Heuristic::LinearConflict,
+        &Stop::NEVER,
+        |g, h| (g + h) as f64,
+    )
+    .unwrap();
+    (res.sequence.len() < window.len()).then_some(res.sequence)
+}
+
+// windows start at `offset` then follow each other every `k` moves
+fn shorten_windows(
+    grid: &Grid,
+    sequence: &[Complex<i32>],
+    k: usize,
+    offset: usize,
+) -> Vec<Complex<i32>> {
+    let mut g = grid.clone();
+    let mut out = Vec::new();
+    let mut i = 0;
+    let mut len = match offset {
+        0 => k,
+        _ => offset,
+    };
+    while i < sequence.len() {
+        let window = &sequence[i..(i + len).min(sequence.len())];
+        let best = solve_window(&g, window);
+        for &d in best.as_deref().unwrap_or(window) {
+            g.op(d);
+            out.push(d);
+        }
+        i += window.len();
+        len = k;
+    }
+    out
+}
+
+// Shortens a solution until neither pass finds anything to remove.
+pub(super) fn shorten(grid: &Grid, sequence: &[Complex<i32>], k: usize) -> Vec<Complex<i32>> {
+    let hasher = Hasher::new(grid.v.len());
+    let mut out = sequence.to_vec();
+    loop {
+        let before = out.len();
+        out = splice_loops(grid, &out, &hasher);
+        if k > 1 {
+            out = shorten_windows(grid, &out, k, 0);
+            out = shorten_windows(grid, &out, k, k / 2);
+        }
+        if out.len() >= before {
+            break;
+        }
+    }
+
+    // two states sharing a hash would splice out a part that is not a loop
+    let (mut a, mut b) = (grid.clone(), grid.clone());
+    sequence.iter().for_each(|&d| a.op(d));
+    let valid = out.iter().all(|&d| {
+        let legal = b.is_op_legal(d);
+        if legal {
+            b.op(d);
+        }
+        legal
+    });
+    match valid && a.v == b.v {
+        true => out,
+        false => sequence.to_vec(),
+    }
+}
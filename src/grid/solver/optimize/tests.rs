@@ -0,0 +1,82 @@
+use super::shorten;
+use crate::{
+    complex::Complex,
+    grid::{
+        solver::{solve, Algo, Heuristic},
+        Grid,
+    },
+};
+
+fn check(g: &Grid, sequence: &[Complex<i32>]) {
+    let mut a = g.clone();
+    let mut b = g.clone();
+    for &op in sequence {
+        a.op(op);
+    }
+    for op in shorten(g, sequence, 10) {
+        assert!(b.is_op_legal(op));
+        b.op(op);
+    }
+    assert!(a.v == b.v);
+}
+
+#[test]
+fn loops() {
+    let g = Grid::create_solved_grid(3, 3);
+    let (r, d, l, u) = (
+        Complex::new(1, 0),
+        Complex::new(0, 1),
+        Complex::new(-1, 0),
+        Complex::new(0, -1),
+    );
+    // the blank starts in the center, going three times around a 2x2 square
+    // brings it back to the same state
+    let around = [u, l, d, r].repeat(3);
+    assert!(shorten(&g, &[r, l, u, d, d, u], 0).is_empty());
+    assert!(shorten(&g, &around, 0).is_empty());
+    // loops are cut as soon as they close, which may miss longer ones
+    let sequence = [[d].as_slice(), &around, &[l]].concat();
+    assert!(shorten(&g, &sequence, 0).len() == 10);
+    assert!(shorten(&g, &sequence, sequence.len()).len() == 2);
+}
+
+#[test]
+fn windows() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        let greedy = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::Greedy,
+            None,
+        )
+        .unwrap();
+        let optimal = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::Astar,
+            None,
+        )
+        .unwrap();
+        let k = greedy.sequence.len();
+        assert!(shorten(&g, &greedy.sequence, k).len() == optimal.sequence.len());
+        check(&g, &greedy.sequence);
+    }
+}
+
+#[test]
+fn large() {
+    let g = Grid::create_solved_grid(12, 12).scramble(10_000);
+    let res = solve(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height),
+        Heuristic::Manhattan,
+        Algo::Constructive,
+        None,
+    )
+    .unwrap();
+    check(&g, &res.sequence);
+    assert!(shorten(&g, &res.sequence, 10).len() < res.sequence.len());
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use super::{Hcost, Heuristic, Res, State};
+use crate::{complex::Complex, grid::Grid};
+
+#[cfg(test)]
+mod tests;
+
+// the frontier is grown until every thread gets this many subtrees to pick
+// from, which keeps them busy when some subtrees are much deeper than others
+const SUBTREES_PER_THREAD: usize = 16;
+
+type Node = (State, Vec<Complex<i32>>);
+
+// state shared by the threads during one iteration, they take the subtrees of
+// the frontier in order through `next`
+struct Shared<'a> {
+    hcost: &'a Hcost,
+    target: &'a Grid,
+    lim: i32,
+    next: AtomicUsize,
+    found: AtomicBool,
+    next_lim: AtomicI32,
+    time_complexity: AtomicUsize,
+    size_complexity: AtomicUsize,
+    solution: Mutex<Option<Vec<Complex<i32>>>>,
+}
+
+struct Worker<'a> {
+    shared: &'a Shared<'a>,
+    seen: HashSet<Vec<i32>>,
+    sequence: Vec<Complex<i32>>,
+    time_complexity: usize,
+    size_complexity: usize,
+}
+
+enum Output {
+    Found,
+    Limit(Option<i32>),
+}
+
+fn compute(w: &mut Worker, s: &State) -> Output {
+    // another thread already found a solution of the same length
+    if w.shared.found.load(Ordering::Relaxed) {
+        return Output::Limit(None);
+    }
+    let f = s.g_cost + s.h_cost;
+    if f > w.shared.lim {
+        return Output::Limit(Some(f));
+    }
+
+    if s.grid.v == w.shared.target.v {
+        return Output::Found;
+    }
+
+    let mut min_lim: Option<i32> = None;
+    let dirs = Grid::dirs();
+    let ops = dirs.iter().filter(|d| s.grid.is_op_legal(**d));
+    for op in ops {
+        w.time_complexity += 1;
+        let mut ns = s.clone();
+        ns.grid.op(*op);
+        if w.seen.contains(&ns.grid.v) {
+            continue;
+        }
+        ns.g_cost += 1;
+        ns.last_op = Some(*op);
+        w.shared.hcost.smart_hcost(s, &mut ns);
+        w.sequence.push(*op);
+        w.seen.insert(ns.grid.v.clone());
+        w.size_complexity = w.size_complexity.max(w.sequence.len());
+        match compute(w, &ns) {
+            Output::Limit(Some(lim)) => min_lim = Some(min_lim.unwrap_or(lim).min(lim)),
+            Output::Limit(None) => {}
+            Output::Found => return Output::Found,
+        }
+        w.sequence.pop();
+        w.seen.remove(&ns.grid.v);
+    }
+    Output::Limit(min_lim)
+}
+
+fn work(shared: &Shared, start: &Grid, frontier: &[Node]) {
+    loop {
+        let i = shared.next.fetch_add(1, Ordering::Relaxed);
+        if i >= frontier.len() || shared.found.load(Ordering::Relaxed) {
+            break;
+        }
+        let (state, sequence) = &frontier[i];
+        let mut w = Worker {
+            shared,
+            seen: HashSet::new(),
+            sequence: sequence.clone(),
+            time_complexity: 0,
+            size_complexity: 0,
+        };
+        let mut g = start.clone();
+        w.seen.insert(g.v.clone());
+        for &op in sequence.iter() {
+            g.op(op);
+            w.seen.insert(g.v.clone());
+        }
+
+        match compute(&mut w, state) {
+            Output::Found => {
+                shared.found.store(true, Ordering::Relaxed);
+                let mut solution = shared.solution.lock().unwrap();
+                if solution.is_none() {
+                    *solution = Some(w.sequence);
+                }
+            }
+            Output::Limit(Some(lim)) => {
+                shared.next_lim.fetch_min(lim, Ordering::Relaxed);
+            }
+            Output::Limit(None) => {}
+        }
+        shared
+            .time_complexity
+            .fetch_add(w.time_complexity, Ordering::Relaxed);
+        shared
+            .size_complexity
+            .fetch_max(w.size_complexity, Ordering::Relaxed);
+    }
+}
+
+// Every iteration searches the subtrees below a fixed frontier in parallel
+// with the same threshold. All the solutions an iteration can find have the
+// same length, so the one that is kept does not change the result.
+pub(super) fn parallel_idastar(grid: &Grid, target: &Grid, h: Heuristic, threads: usize) -> Res {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let hcost = Hcost::new(target, h);
+    let threads = threads.max(1);
+
+    // the frontier is grown one full level at a time, a solution found on the
+    // way is then a shortest one
+    let mut state = State {
+        grid: grid.clone(),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    hcost.init_hcost(&mut state);
+    let mut lim = state.h_cost;
+    let mut frontier: Vec<Node> = vec![(state, Vec::new())];
+    while frontier.len() < threads * SUBTREES_PER_THREAD {
+        if let Some((_, sequence)) = frontier.iter().find(|(s, _)| s.grid.v == target.v) {
+            res.sequence = sequence.clone();
+            return res;
+        }
+        let mut next = Vec::new();
+        for (s, sequence) in frontier.iter() {
+            let dirs = Grid::dirs();
+            let ops = dirs
+                .iter()
+                .filter(|d| s.grid.is_op_legal(**d) && Some(**d * -1) != s.last_op);
+            for op in ops {
+                res.time_complexity += 1;
+                let mut ns = s.clone();
+                ns.grid.op(*op);
+                ns.g_cost += 1;
+                ns.last_op = Some(*op);
+                hcost.smart_hcost(s, &mut ns);
+                let mut nsequence = sequence.clone();
+                nsequence.push(*op);
+                next.push((ns, nsequence));
+            }
+        }
+        frontier = next;
+    }
+    res.size_complexity = frontier.len();
+
+    loop {
+        let shared = Shared {
+            hcost: &hcost,
+            target,
+            lim,
+            next: AtomicUsize::new(0),
+            found: AtomicBool::new(false),
+            next_lim: AtomicI32::new(i32::MAX),
+            time_complexity: AtomicUsize::new(0),
+            size_complexity: AtomicUsize::new(0),
+            solution: Mutex::new(None),
+        };
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| work(&shared, grid, &frontier));
+            }
+        });
+
+        res.time_complexity += shared.time_complexity.into_inner();
+        res.size_complexity = res.size_complexity.max(shared.size_complexity.into_inner());
+        if let Some(sequence) = shared.solution.into_inner().unwrap() {
+            res.sequence = sequence;
+            return res;
+        }
+        lim = shared.next_lim.into_inner();
+    }
+}
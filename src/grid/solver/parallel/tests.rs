@@ -0,0 +1,41 @@
+use super::parallel_idastar;
+use crate::{
+    complex::Complex,
+    grid::{
+        solver::{tests::check_optimal, Heuristic},
+        Grid,
+    },
+};
+
+fn check(g: &Grid, h: Heuristic, threads: usize) {
+    let res = parallel_idastar(g, &Grid::create_solved_grid(g.width, g.height), h, threads);
+    check_optimal(g, &res);
+}
+
+#[test]
+fn shallow() {
+    for n in 0..6 {
+        let g = Grid::create_solved_grid(3, 3).scramble(n);
+        check(&g, Heuristic::Manhattan, 8);
+    }
+}
+
+#[test]
+fn optimal() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        check(&g, Heuristic::Manhattan, 1);
+        check(&g, Heuristic::LinearConflict, 4);
+    }
+}
+
+#[test]
+fn optimal_4() {
+    let g = Grid {
+        width: 4,
+        height: 4,
+        v: vec![12, 1, 2, 4, 11, 13, 6, 5, 10, 9, 3, 0, 8, 15, 7, 14],
+        zero: Complex::new(3, 2),
+    };
+    check(&g, Heuristic::LinearConflict, 4);
+}
@@ -0,0 +1,379 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+    sync::{Arc, Mutex},
+};
+
+use super::super::Grid;
+use crate::complex::Complex;
+
+#[cfg(test)]
+mod tests;
+
+// A pattern plus the blank has to fit in a packed state of the breadth-first
+// search, hence at most 7 tiles per group.
+const MAX_PATTERN: usize = 7;
+
+// positions are stored as bytes
+const MAX_CELLS: usize = u8::MAX as usize + 1;
+
+// Files start with this magic and version, then the board width and height,
+// the goal layout and the number of groups as little-endian u32. Each group is
+// its tile count as a u8, the tiles as u32, the bits per entry (4 or 8) as a
+// u8 and the packed table, low nibble first. Versions 1 and 2 left the blank
+// out of the tables.
+const MAGIC: &[u8; 4] = b"NPDB";
+const VERSION: u8 = 3;
+
+static CACHE: Mutex<Vec<Arc<Pdb>>> = Mutex::new(Vec::new());
+
+pub enum PdbError {
+    Io(io::Error),
+    Format,
+    Outdated,
+    GoalMismatch,
+    TooLarge,
+    Bidirectional,
+}
+
+impl fmt::Display for PdbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "pattern database : {}", e),
+            Self::Format => write!(f, "pattern database : invalid file"),
+            Self::Outdated => write!(f, "pattern database : outdated file, build it again"),
+            Self::GoalMismatch => write!(f, "pattern database : goal layout mismatch"),
+            Self::TooLarge => write!(
+                f,
+                "pattern database : boards are limited to {} cells",
+                MAX_CELLS
+            ),
+            Self::Bidirectional => write!(
+                f,
+                "pattern database : the bidirectional search would need one toward every start"
+            ),
+        }
+    }
+}
+
+impl Error for PdbError {}
+
+impl fmt::Debug for PdbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", *self)
+    }
+}
+
+impl From<io::Error> for PdbError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+// the length comes from the header, so the buffer only grows with the bytes
+// actually read instead of being allocated upfront
+fn read_table<R: Read>(r: &mut R, len: usize) -> Result<Vec<u8>, PdbError> {
+    let mut buf = Vec::new();
+    r.take(len as u64).read_to_end(&mut buf)?;
+    match buf.len() == len {
+        true => Ok(buf),
+        false => Err(PdbError::Format),
+    }
+}
+
+struct Pattern {
+    tiles: Vec<i32>,
+    table: Vec<u8>,
+}
+
+pub struct Pdb {
+    width: i32,
+    height: i32,
+    goal: Vec<i32>,
+    patterns: Vec<Pattern>,
+    owner: Vec<Option<usize>>,
+}
+
+fn permutations(cells: usize, k: usize) -> usize {
+    (cells + 1 - k..=cells).product()
+}
+
+fn rank(cells: usize, positions: &[u8]) -> usize {
+    let mut r = 0;
+    for (i, &p) in positions.iter().enumerate() {
+        let smaller = positions[..i].iter().filter(|&&q| q < p).count();
+        r = r * (cells - i) + p as usize - smaller;
+    }
+    r
+}
+
+impl Pattern {
+    fn build(goal: &Grid, tiles: Vec<i32>) -> Self {
+        let width = goal.width;
+        let cells = goal.v.len();
+        let k = tiles.len();
+        let position = |n: i32| goal.v.iter().position(|&v| v == n).unwrap() as u8;
+
+        let mut start = [0; MAX_PATTERN + 1];
+        for (i, &t) in tiles.iter().enumerate() {
+            start[i] = position(t);
+        }
+        start[k] = position(0);
+
+        // 0-1 breadth-first search : only the moves of the pattern tiles are
+        // counted, which keeps the groups additive
+        let mut dist = vec![u8::MAX; permutations(cells, k + 1)];
+        dist[rank(cells, &start[..=k])] = 0;
+        let mut queue = VecDeque::from([start]);
+        while let Some(s) = queue.pop_front() {
+            let c = dist[rank(cells, &s[..=k])];
+            let blank = Complex::new(s[k] as i32 % width, s[k] as i32 / width);
+            for d in Grid::dirs() {
+                let p = blank + d;
+                if !goal.contains(p) {
+                    continue;
+                }
+                let mut ns = s;
+                ns[k] = (p.y * width + p.x) as u8;
+                let moved = s[..k].iter().position(|&q| q == ns[k]);
+                if let Some(i) = moved {
+                    ns[i] = s[k];
+                }
+                let nc = c + moved.is_some() as u8;
+                let r = rank(cells, &ns[..=k]);
+                if nc < dist[r] {
+                    dist[r] = nc;
+                    match moved {
+                        Some(_) => queue.push_back(ns),
+                        None => queue.push_front(ns),
+                    }
+                }
+            }
+        }
+
+        // the blank stays in the table : taking the smallest entry over its
+        // positions is still admissible but not consistent anymore, a single
+        // move could then lower the cost by more than one
+        Self { tiles, table: dist }
+    }
+
+    fn lookup(&self, positions: &[u8]) -> i32 {
+        let k = self.tiles.len();
+        let mut p = [0; MAX_PATTERN + 1];
+        for (i, &t) in self.tiles.iter().enumerate() {
+            p[i] = positions[t as usize];
+        }
+        p[k] = positions[0];
+        self.table[rank(positions.len(), &p[..=k])] as i32
+    }
+}
+
+impl Pdb {
+    pub fn default_partition(width: i32, height: i32) -> Vec<Vec<i32>> {
+        let k = match width * height {
+            ..=9 => 4,
+            10..=16 => 6,
+            17..=25 => 4,
+            _ => 3,
+        };
+        let tiles = (1..width * height).collect::<Vec<_>>();
+        tiles.chunks(k).map(|c| c.to_vec()).collect()
+    }
+
+    pub fn check(goal: &Grid) -> Result<(), PdbError> {
+        match goal.v.len() <= MAX_CELLS {
+            true => Ok(()),
+            false => Err(PdbError::TooLarge),
+        }
+    }
+
+    pub fn build(goal: &Grid, partition: Vec<Vec<i32>>) -> Result<Self, PdbError> {
+        Self::check(goal)?;
+        let mut owner = vec![None; goal.v.len()];
+        for (i, group) in partition.iter().enumerate() {
+            assert!(group.len() <= MAX_PATTERN);
+            for &t in group {
+                owner[t as usize] = Some(i);
+            }
+        }
+        Ok(Self {
+            width: goal.width,
+            height: goal.height,
+            goal: goal.v.clone(),
+            patterns: partition
+                .into_iter()
+                .map(|tiles| Pattern::build(goal, tiles))
+                .collect(),
+            owner,
+        })
+    }
+
+    pub fn get(goal: &Grid) -> Result<Arc<Self>, PdbError> {
+        let mut cache = CACHE.lock().unwrap();
+        if let Some(pdb) = cache.iter().find(|pdb| pdb.fits(goal)) {
+            return Ok(pdb.clone());
+        }
+        let pdb = Arc::new(Self::build(
+            goal,
+            Self::default_partition(goal.width, goal.height),
+        )?);
+        cache.push(pdb.clone());
+        Ok(pdb)
+    }
+
+    pub fn fits(&self, goal: &Grid) -> bool {
+        (self.width, self.height) == (goal.width, goal.height) && self.goal == goal.v
+    }
+
+    pub fn register(self) -> Arc<Self> {
+        let mut cache = CACHE.lock().unwrap();
+        cache.retain(|pdb| {
+            (pdb.width, pdb.height, &pdb.goal) != (self.width, self.height, &self.goal)
+        });
+        let pdb = Arc::new(self);
+        cache.push(pdb.clone());
+        pdb
+    }
+
+    pub fn save<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+        w.write_all(&(self.width as u32).to_le_bytes())?;
+        w.write_all(&(self.height as u32).to_le_bytes())?;
+        for &n in self.goal.iter() {
+            w.write_all(&(n as u32).to_le_bytes())?;
+        }
+        w.write_all(&(self.patterns.len() as u32).to_le_bytes())?;
+        for p in self.patterns.iter() {
+            w.write_all(&[p.tiles.len() as u8])?;
+            for &t in p.tiles.iter() {
+                w.write_all(&(t as u32).to_le_bytes())?;
+            }
+            if p.table.iter().all(|&c| c < 16) {
+                w.write_all(&[4])?;
+                let packed = p
+                    .table
+                    .chunks(2)
+                    .map(|c| c[0] | c.get(1).unwrap_or(&0) << 4)
+                    .collect::<Vec<_>>();
+                w.write_all(&packed)?;
+            } else {
+                w.write_all(&[8])?;
+                w.write_all(&p.table)?;
+            }
+        }
+        w.flush()
+    }
+
+    pub fn load<R: Read>(mut r: R) -> Result<Self, PdbError> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(PdbError::Format);
+        }
+        let (width, height) = match read_u8(&mut r)? {
+            1 | 2 => return Err(PdbError::Outdated),
+            VERSION => (read_u32(&mut r)?, read_u32(&mut r)?),
+            _ => return Err(PdbError::Format),
+        };
+        // each side is checked first so the product cannot overflow
+        let sides = 1..=MAX_CELLS as u32;
+        if !sides.contains(&width) || !sides.contains(&height) || width * height > MAX_CELLS as u32
+        {
+            return Err(PdbError::Format);
+        }
+        let (width, height) = (width as i32, height as i32);
+        let goal = (0..width * height)
+            .map(|_| read_u32(&mut r).map(|n| n as i32))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut sorted = goal.clone();
+        sorted.sort();
+        if !sorted.iter().copied().eq(0..width * height) {
+            return Err(PdbError::Format);
+        }
+
+        let cells = goal.len();
+        let mut owner = vec![None; cells];
+        let mut patterns = Vec::new();
+        for i in 0..read_u32(&mut r)? as usize {
+            let k = read_u8(&mut r)? as usize;
+            if !(1..=MAX_PATTERN.min(cells - 1)).contains(&k) {
+                return Err(PdbError::Format);
+            }
+            let mut tiles = Vec::new();
+            for _ in 0..k {
+                let t = read_u32(&mut r)? as usize;
+                if !(1..cells).contains(&t) || owner[t].is_some() {
+                    return Err(PdbError::Format);
+                }
+                owner[t] = Some(i);
+                tiles.push(t as i32);
+            }
+            let len = permutations(cells, k + 1);
+            let table = match read_u8(&mut r)? {
+                4 => {
+                    let packed = read_table(&mut r, len.div_ceil(2))?;
+                    (0..len)
+                        .map(|j| packed[j / 2] >> (j % 2 * 4) & 15)
+                        .collect()
+                }
+                8 => read_table(&mut r, len)?,
+                _ => return Err(PdbError::Format),
+            };
+            patterns.push(Pattern { tiles, table });
+        }
+        if r.read(&mut [0])? != 0 {
+            return Err(PdbError::Format);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            goal,
+            patterns,
+            owner,
+        })
+    }
+
+    fn positions(grid: &Grid) -> Vec<u8> {
+        let mut positions = vec![0; grid.v.len()];
+        for (i, &n) in grid.v.iter().enumerate() {
+            positions[n as usize] = i as u8;
+        }
+        positions
+    }
+
+    pub fn hcost(&self, grid: &Grid) -> i32 {
+        let positions = Self::positions(grid);
+        self.patterns.iter().map(|p| p.lookup(&positions)).sum()
+    }
+
+    // `grid` is taken before the move, only the group of the tile sliding
+    // from `from` into the blank at `to` is looked up again : the blank moves
+    // freely around the tiles of the other groups
+    pub fn smart_hcost(&self, h: i32, grid: &Grid, from: Complex<i32>, to: Complex<i32>) -> i32 {
+        let tile = *grid.get_cell_ref(from);
+        let Some(i) = self.owner[tile as usize] else {
+            return h;
+        };
+        let mut positions = Self::positions(grid);
+        let before = self.patterns[i].lookup(&positions);
+        positions[tile as usize] = (to.y * self.width + to.x) as u8;
+        positions[0] = (from.y * self.width + from.x) as u8;
+        h - before + self.patterns[i].lookup(&positions)
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+
+use super::{permutations, rank, Pdb, PdbError};
+use crate::{
+    complex::Complex,
+    grid::{
+        solver::{solve, solve_anytime, Algo, Hcost, Heuristic, SolveError},
+        Grid, Layout,
+    },
+};
+
+#[test]
+fn rank_is_dense() {
+    let mut hs = HashSet::new();
+    for a in 0..5 {
+        for b in (0..5).filter(|&b| b != a) {
+            for c in (0..5).filter(|&c| c != a && c != b) {
+                let r = rank(5, &[a, b, c]);
+                assert!(r < permutations(5, 3));
+                hs.insert(r);
+            }
+        }
+    }
+    assert!(hs.len() == permutations(5, 3));
+}
+
+#[test]
+fn default_partition() {
+    let lens = |width, height| {
+        Pdb::default_partition(width, height)
+            .iter()
+            .map(|g| g.len())
+            .collect::<Vec<_>>()
+    };
+    assert!(lens(3, 3) == vec![4, 4]);
+    assert!(lens(4, 4) == vec![6, 6, 3]);
+    assert!(lens(3, 5) == vec![6, 6, 2]);
+    assert!(lens(4, 6) == vec![4, 4, 4, 4, 4, 3]);
+}
+
+#[test]
+fn solved_is_zero() {
+    let pdb = Pdb::get(&Grid::create_solved_grid(3, 3)).unwrap();
+    assert!(pdb.hcost(&Grid::create_solved_grid(3, 3)) == 0);
+}
+
+#[test]
+fn dominates_manhattan() {
+    let pdb = Pdb::get(&Grid::create_solved_grid(3, 3)).unwrap();
+    let manhattan = Hcost::new(&Grid::create_solved_grid(3, 3), Heuristic::Manhattan);
+    for _ in 0..20 {
+        let g = Grid::create_solved_grid(3, 3).scramble(100);
+        let h = pdb.hcost(&g);
+        assert!(h >= manhattan.hcost(&g));
+        let res = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::Astar,
+            None,
+        )
+        .unwrap();
+        assert!(h as usize <= res.sequence.len());
+    }
+}
+
+#[test]
+fn rectangle() {
+    let goal = Grid::create_solved_grid(2, 4);
+    let pdb = Pdb::get(&goal).unwrap();
+    let manhattan = Hcost::new(&goal, Heuristic::Manhattan);
+    assert!(pdb.fits(&goal) && !pdb.fits(&Grid::create_solved_grid(4, 2)));
+    for _ in 0..20 {
+        let g = Grid::create_solved_grid(2, 4).scramble(100);
+        let h = pdb.hcost(&g);
+        assert!(h >= manhattan.hcost(&g));
+        let res = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::Astar,
+            None,
+        )
+        .unwrap();
+        assert!(h as usize <= res.sequence.len());
+    }
+}
+
+#[test]
+fn transposed() {
+    // row-major goals of a board and of its transpose share the same vector
+    for (width, height) in [(2, 3), (3, 2), (2, 4), (4, 2)] {
+        let goal = Grid::create_goal(width, height, Layout::RowMajor);
+        assert!(Pdb::get(&goal).unwrap().fits(&goal));
+        let g = goal.scramble(100);
+        let res = solve(&g, &goal, Heuristic::PatternDatabase, Algo::Astar, None).unwrap();
+        let manhattan = solve(&g, &goal, Heuristic::Manhattan, Algo::Astar, None).unwrap();
+        assert!(res.length() == manhattan.length());
+    }
+}
+
+#[test]
+fn too_large() {
+    let goal = Grid::create_solved_grid(17, 17);
+    assert!(matches!(
+        Pdb::build(&goal, vec![vec![1, 2]]),
+        Err(PdbError::TooLarge)
+    ));
+    let g = goal.scramble(10);
+    let res = solve(&g, &goal, Heuristic::PatternDatabase, Algo::Beam(10), None);
+    assert!(matches!(res, Err(SolveError::Pdb(PdbError::TooLarge))));
+}
+
+#[test]
+fn smart_hcost() {
+    let goal = Grid::create_solved_grid(4, 4);
+    let pdb = Pdb::build(&goal, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]).unwrap();
+    let mut g = goal.clone();
+    let mut h = 0;
+    let mut last = Complex::new(0, 0);
+    for _ in 0..200 {
+        let d = *Grid::dirs()
+            .iter()
+            .find(|d| g.is_op_legal(**d) && **d * -1 != last)
+            .unwrap();
+        h = pdb.smart_hcost(h, &g, g.zero + d, g.zero);
+        g.op(d);
+        last = d;
+        assert!(h == pdb.hcost(&g));
+    }
+}
+
+#[test]
+fn consistent() {
+    for (width, height) in [(3, 3), (4, 2), (5, 2)] {
+        let goal = Grid::create_solved_grid(width, height);
+        let pdb = Pdb::get(&goal).unwrap();
+        let mut g = goal.scramble(1000);
+        let mut h = pdb.hcost(&g);
+        for _ in 0..2000 {
+            g = g.scramble(1);
+            let next = pdb.hcost(&g);
+            assert!((h - next).abs() <= 1);
+            h = next;
+        }
+    }
+}
+
+#[test]
+fn optimal() {
+    // the smallest entry over the blank positions made A* miss the 25 moves
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![8, 3, 7, 5, 4, 6, 1, 0, 2],
+        zero: Complex::new(1, 2),
+    };
+    let goal = Grid::create_solved_grid(3, 3);
+    for algo in [Algo::Astar, Algo::IDAstar] {
+        let res = solve(&g, &goal, Heuristic::PatternDatabase, algo, None).unwrap();
+        assert!(res.length() == 25);
+    }
+    assert!(matches!(
+        solve(
+            &g,
+            &goal,
+            Heuristic::PatternDatabase,
+            Algo::Bidirectional,
+            None
+        ),
+        Err(SolveError::Pdb(PdbError::Bidirectional))
+    ));
+    let res = solve_anytime(&g, &goal, Heuristic::PatternDatabase, 1., None, |_, _| {}).unwrap();
+    assert!(res.length() == 25);
+}
+
+#[test]
+fn solve_4() {
+    let g = Grid {
+        width: 4,
+        height: 4,
+        v: vec![12, 1, 2, 4, 11, 13, 6, 5, 10, 9, 3, 0, 8, 15, 7, 14],
+        zero: Complex::new(3, 2),
+    };
+    let pdb = Pdb::build(
+        &Grid::create_solved_grid(4, 4),
+        vec![
+            vec![1, 2, 3, 4],
+            vec![5, 6, 7, 8],
+            vec![9, 10, 11, 12],
+            vec![13, 14, 15],
+        ],
+    )
+    .unwrap();
+    let h = pdb.hcost(&g);
+    assert!(h >= Hcost::new(&Grid::create_solved_grid(4, 4), Heuristic::Manhattan).hcost(&g));
+    assert!(h <= 22);
+}
+
+#[test]
+fn save_load() {
+    let goal = Grid::create_solved_grid(3, 3);
+    let pdb = Pdb::build(&goal, vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]).unwrap();
+    let mut buf = Vec::new();
+    pdb.save(&mut buf).unwrap();
+    let loaded = Pdb::load(buf.as_slice()).unwrap();
+    assert!(loaded.goal == goal.v);
+
+    // the tables of older versions left the blank out
+    let mut v2 = buf.clone();
+    v2[4] = 2;
+    assert!(matches!(Pdb::load(v2.as_slice()), Err(PdbError::Outdated)));
+    for _ in 0..20 {
+        let g = Grid::create_solved_grid(3, 3).scramble(100);
+        assert!(loaded.hcost(&g) == pdb.hcost(&g));
+    }
+}
+
+#[test]
+fn load_invalid() {
+    let goal = Grid::create_solved_grid(3, 3);
+    let pdb = Pdb::build(&goal, vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8]]).unwrap();
+    let mut buf = Vec::new();
+    pdb.save(&mut buf).unwrap();
+
+    assert!(matches!(
+        Pdb::load(&buf[..buf.len() - 1]),
+        Err(PdbError::Format)
+    ));
+    assert!(matches!(Pdb::load(&buf[..8]), Err(PdbError::Io(_))));
+    assert!(matches!(Pdb::load(&b"NPDB\x04"[..]), Err(PdbError::Format)));
+
+    // dimensions whose product overflows
+    let mut huge = buf.clone();
+    huge[5..9].copy_from_slice(&0x10000u32.to_le_bytes());
+    huge[9..13].copy_from_slice(&0x10000u32.to_le_bytes());
+    assert!(matches!(Pdb::load(huge.as_slice()), Err(PdbError::Format)));
+    let mut wide = buf.clone();
+    wide[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(Pdb::load(wide.as_slice()), Err(PdbError::Format)));
+
+    // a small file declaring a table too large to allocate
+    let mut big = Vec::new();
+    big.extend_from_slice(&buf[..5]);
+    big.extend_from_slice(&16u32.to_le_bytes());
+    big.extend_from_slice(&16u32.to_le_bytes());
+    for n in 0..256u32 {
+        big.extend_from_slice(&n.to_le_bytes());
+    }
+    big.extend_from_slice(&1u32.to_le_bytes());
+    big.push(7);
+    for t in 1..8u32 {
+        big.extend_from_slice(&t.to_le_bytes());
+    }
+    big.push(8);
+    big.extend_from_slice(&[0; 64]);
+    assert!(matches!(Pdb::load(big.as_slice()), Err(PdbError::Format)));
+
+    // the goal layout is stored right after the header, any permutation of
+    // the tiles is a goal
+    let mut swapped = buf.clone();
+    swapped[13..17].copy_from_slice(&2u32.to_le_bytes());
+    swapped[17..21].copy_from_slice(&1u32.to_le_bytes());
+    assert!(!Pdb::load(swapped.as_slice()).unwrap().fits(&goal));
+    let mut duplicated = buf.clone();
+    duplicated[13..17].copy_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(
+        Pdb::load(duplicated.as_slice()),
+        Err(PdbError::Format)
+    ));
+}
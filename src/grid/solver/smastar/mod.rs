@@ -0,0 +1,258 @@
+use std::{cmp::Reverse, collections::BTreeSet};
+
+use super::{Hcost, Heuristic, Res, SolveError, State};
+use crate::grid::Grid;
+
+#[cfg(test)]
+mod tests;
+
+const INF: i32 = i32::MAX;
+
+type Key = (i32, Reverse<i32>, usize);
+
+struct Node {
+    state: State,
+    f: i32,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    forgotten: Option<i32>,
+    expanded: bool,
+    open_key: Option<Key>,
+    leaf_key: Option<Key>,
+}
+
+// Nodes waiting for an expansion sit in `open_set` : the leaves that were
+// never expanded, keyed by their f cost, and the nodes that had children
+// dropped, keyed by the smallest f cost they forgot. The first entry is the
+// best node, deepest first on ties, the last entry of `leaves` is the worst
+// leaf, shallowest first on ties.
+struct Sma {
+    hcost: Hcost,
+    nodes: Vec<Option<Node>>,
+    free: Vec<usize>,
+    used: usize,
+    open_set: BTreeSet<Key>,
+    leaves: BTreeSet<Key>,
+    expanding: Option<usize>,
+}
+
+impl Sma {
+    fn node(&self, id: usize) -> &Node {
+        self.nodes[id].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, id: usize) -> &mut Node {
+        self.nodes[id].as_mut().unwrap()
+    }
+
+    fn set_open(&mut self, id: usize, key: Option<i32>) {
+        if let Some(k) = self.node_mut(id).open_key.take() {
+            self.open_set.remove(&k);
+        }
+        if let Some(key) = key {
+            let k = (key, Reverse(self.node(id).state.g_cost), id);
+            self.open_set.insert(k);
+            self.node_mut(id).open_key = Some(k);
+        }
+    }
+
+    fn set_leaf(&mut self, id: usize, leaf: bool) {
+        if let Some(k) = self.node_mut(id).leaf_key.take() {
+            self.leaves.remove(&k);
+        }
+        if leaf {
+            let n = self.node(id);
+            let k = (n.f, Reverse(n.state.g_cost), id);
+            self.leaves.insert(k);
+            self.node_mut(id).leaf_key = Some(k);
+        }
+    }
+
+    fn insert(&mut self, state: State, f: i32, parent: Option<usize>) -> usize {
+        let node = Node {
+            state,
+            f,
+            parent,
+            children: Vec::new(),
+            forgotten: None,
+            expanded: false,
+            open_key: None,
+            leaf_key: None,
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Some(node);
+                id
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.used += 1;
+        if let Some(p) = parent {
+            self.node_mut(p).children.push(id);
+        }
+        self.set_open(id, Some(f));
+        self.set_leaf(id, true);
+        id
+    }
+
+    // drops a leaf and remembers its f cost in its parent
+    fn forget(&mut self, id: usize) {
+        self.set_open(id, None);
+        self.set_leaf(id, false);
+        let node = self.nodes[id].take().unwrap();
+        self.free.push(id);
+        self.used -= 1;
+        let p = node.parent.unwrap();
+        let parent = self.node_mut(p);
+        parent.children.retain(|&c| c != id);
+        parent.forgotten = Some(parent.forgotten.unwrap_or(INF).min(node.f));
+        let (forgotten, childless) = (parent.forgotten, parent.children.is_empty());
+        self.set_open(p, forgotten);
+        if childless && self.expanding != Some(p) {
+            self.set_leaf(p, true);
+        }
+    }
+
+    // the f cost of a node is the smallest f cost below it, whether the child
+    // is still in memory or forgotten
+    fn backup(&mut self, mut id: usize) {
+        loop {
+            let n = self.node(id);
+            let f = n
+                .children
+                .iter()
+                .map(|&c| self.node(c).f)
+                .chain(n.forgotten)
+                .min()
+                .unwrap_or(INF)
+                .max(n.f);
+            if f == n.f {
+                break;
+            }
+            self.node_mut(id).f = f;
+            if self.node(id).leaf_key.is_some() {
+                self.set_leaf(id, true);
+            }
+            match self.node(id).parent {
+                Some(p) => id = p,
+                None => break,
+            }
+        }
+    }
+}
+
+pub(super) fn smastar(
+    grid: &Grid,
+    target: &Grid,
+    h: Heuristic,
+    max_states: usize,
+) -> Result<Res, SolveError> {
+    let mut res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: Vec::new(),
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let mut sma = Sma {
+        hcost: Hcost::new(target, h),
+        nodes: Vec::new(),
+        free: Vec::new(),
+        used: 0,
+        open_set: BTreeSet::new(),
+        leaves: BTreeSet::new(),
+        expanding: None,
+    };
+    let mut root = State {
+        grid: grid.clone(),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    sma.hcost.init_hcost(&mut root);
+    let f = root.h_cost;
+    sma.insert(root, f, None);
+
+    loop {
+        let Some(&(key, _, id)) = sma.open_set.first() else {
+            return Err(SolveError::OutOfMemory(max_states));
+        };
+        if key == INF {
+            return Err(SolveError::OutOfMemory(max_states));
+        }
+        if sma.node(id).state.grid.v == target.v {
+            let mut id = Some(id);
+            while let Some(n) = id.map(|id| sma.node(id)) {
+                res.sequence.extend(n.state.last_op);
+                id = n.parent;
+            }
+            res.sequence.reverse();
+            return Ok(res);
+        }
+        res.time_complexity += 1;
+
+        // a first expansion generates every successor, a later one only
+        // brings back the forgotten ones, with the f cost they were forgotten
+        // with as a lower bound
+        let s = sma.node(id).state.clone();
+        let bound = match sma.node(id).expanded {
+            false => sma.node(id).f,
+            true => sma.node(id).forgotten.unwrap(),
+        };
+        let kept = sma
+            .node(id)
+            .children
+            .iter()
+            .map(|&c| sma.node(c).state.last_op)
+            .collect::<Vec<_>>();
+        sma.set_open(id, None);
+        sma.set_leaf(id, false);
+        sma.expanding = Some(id);
+        let n = sma.node_mut(id);
+        n.expanded = true;
+        n.forgotten = None;
+
+        let dirs = Grid::dirs();
+        let ops = dirs.iter().filter(|d| {
+            s.grid.is_op_legal(**d) && Some(**d * -1) != s.last_op && !kept.contains(&Some(**d))
+        });
+        for op in ops {
+            let mut ns = s.clone();
+            ns.grid.op(*op);
+            ns.g_cost += 1;
+            ns.last_op = Some(*op);
+            sma.hcost.smart_hcost(&s, &mut ns);
+            let f = match ns.grid.v == target.v || (ns.g_cost as usize) + 1 < max_states {
+                true => (ns.g_cost + ns.h_cost).max(bound),
+                false => INF,
+            };
+
+            // make room by dropping the worst leaf, unless the new child is
+            // even worse
+            if sma.used >= max_states {
+                match sma.leaves.last() {
+                    Some(&(worst, _, w)) if worst >= f => sma.forget(w),
+                    _ => {
+                        let n = sma.node_mut(id);
+                        n.forgotten = Some(n.forgotten.unwrap_or(INF).min(f));
+                        continue;
+                    }
+                }
+            }
+            sma.insert(ns, f, Some(id));
+        }
+
+        sma.expanding = None;
+        let n = sma.node(id);
+        let (forgotten, childless) = (n.forgotten, n.children.is_empty());
+        sma.set_open(id, forgotten);
+        sma.set_leaf(id, childless);
+        sma.backup(id);
+        res.size_complexity = res.size_complexity.max(sma.used);
+    }
+}
@@ -0,0 +1,43 @@
+use super::smastar;
+use crate::grid::{
+    solver::{tests::check_optimal, Heuristic},
+    Grid,
+};
+
+fn check(g: &Grid, h: Heuristic, max_states: usize) {
+    let res = smastar(
+        g,
+        &Grid::create_solved_grid(g.width, g.height),
+        h,
+        max_states,
+    )
+    .unwrap();
+    check_optimal(g, &res);
+    assert!(res.size_complexity <= max_states);
+}
+
+#[test]
+fn optimal() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        check(&g, Heuristic::Manhattan, 100_000);
+        check(&g, Heuristic::LinearConflict, 1000);
+    }
+}
+
+#[test]
+fn bounded() {
+    for _ in 0..5 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        check(&g, Heuristic::LinearConflict, 100);
+    }
+}
+
+#[test]
+fn out_of_memory() {
+    let g = Grid::create_solved_grid(3, 3).scramble(200);
+    let target = Grid::create_solved_grid(3, 3);
+    if g.v != target.v {
+        assert!(smastar(&g, &target, Heuristic::Manhattan, 1).is_err());
+    }
+}
@@ -1,57 +1,294 @@
+use std::time::Duration;
+
+use serde_json::json;
+
 use crate::{
     complex::Complex,
     grid::{
-        solver::{is_solvable, solve, Algo, Heuristic},
-        Grid,
+        solver::{
+            solvability, solve, solve_anytime, Algo, Hcost, Heuristic, Res, SolveError, State,
+            Stop, Unsolvable,
+        },
+        Grid, Layout,
     },
 };
 
+fn is_solvable(grid: &Grid, target: &Grid) -> bool {
+    solvability(grid, target).is_ok()
+}
+
 #[test]
 fn solvable_3() {
     let g = Grid {
-        size: 3,
+        width: 3,
+        height: 3,
         v: vec![8, 4, 2, 3, 0, 5, 6, 7, 1],
         zero: Complex::new(1, 1),
     };
-    assert!(is_solvable(&g));
+    assert!(is_solvable(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height)
+    ));
 }
 
 #[test]
 fn solvable_4() {
     let g = Grid {
-        size: 4,
+        width: 4,
+        height: 4,
         v: vec![3, 11, 13, 6, 14, 4, 0, 15, 7, 12, 1, 9, 8, 10, 2, 5],
         zero: Complex::new(2, 1),
     };
-    assert!(is_solvable(&g));
+    assert!(is_solvable(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height)
+    ));
 }
 
 #[test]
 fn unsolvable_3() {
     let g = Grid {
-        size: 3,
+        width: 3,
+        height: 3,
         v: vec![6, 4, 0, 2, 7, 3, 5, 1, 8],
         zero: Complex::new(1, 1),
     };
-    assert!(!is_solvable(&g));
+    assert!(!is_solvable(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height)
+    ));
 }
 
 #[test]
 fn unsolvable_4() {
     let g = Grid {
-        size: 4,
+        width: 4,
+        height: 4,
         v: vec![4, 14, 8, 6, 5, 12, 3, 7, 9, 1, 15, 10, 13, 11, 0, 2],
         zero: Complex::new(2, 3),
     };
-    assert!(!is_solvable(&g));
+    assert!(!is_solvable(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height)
+    ));
+}
+
+#[test]
+fn unsolvable_explanation() {
+    let goal = Grid::create_solved_grid(3, 3);
+    // two tiles swapped with the blank in place
+    let mut g = goal.clone();
+    g.v.swap(0, 1);
+    match solvability(&g, &goal) {
+        Err(Unsolvable::Parity {
+            inversions,
+            distance,
+            row,
+            goal_row,
+        }) => assert!((inversions, distance, row, goal_row) == (1, 0, 1, 1)),
+        _ => panic!("expected a parity mismatch"),
+    }
+    // one move and a swap away
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![2, 1, 3, 8, 4, 0, 7, 6, 5],
+        zero: Complex::new(2, 1),
+    };
+    let why = solvability(&g, &goal).unwrap_err().to_string();
+    assert!(why.contains("2 inversions (even)"));
+    assert!(why.contains("at distance 1 from its goal cell (odd)"));
+    assert!(why.contains("on its goal row"));
+    assert!(!why.contains("instead of"));
+    // the blank one row up and a swap away
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![1, 0, 3, 8, 2, 4, 6, 7, 5],
+        zero: Complex::new(1, 0),
+    };
+    let why = solvability(&g, &goal).unwrap_err().to_string();
+    assert!(why.contains("on row 1 instead of row 2 (odd row offset)"));
+    let line = Grid {
+        width: 4,
+        height: 1,
+        v: vec![1, 3, 0, 2],
+        zero: Complex::new(2, 0),
+    };
+    assert!(matches!(
+        solvability(&line, &Grid::create_solved_grid(4, 1)),
+        Err(Unsolvable::Line(1))
+    ));
+    assert!(matches!(
+        solvability(&goal, &Grid::create_solved_grid(3, 4)),
+        Err(Unsolvable::Dimensions((3, 3), (3, 4)))
+    ));
+    assert!(matches!(
+        solve(&g, &goal, Heuristic::Manhattan, Algo::Astar, None),
+        Err(SolveError::Unsolvable(Unsolvable::Parity { .. }))
+    ));
 }
 
-fn check_sequence(g: &Grid, sequence: &Vec<Complex<i32>>) {
+#[test]
+fn solvable_rectangle() {
+    for (width, height) in [(2, 3), (3, 2), (3, 5), (4, 6), (6, 4), (2, 8)] {
+        for _ in 0..10 {
+            let mut g = Grid::create_solved_grid(width, height).scramble(500);
+            assert!(is_solvable(
+                &g,
+                &Grid::create_solved_grid(g.width, g.height)
+            ));
+            // swapping two tiles changes the parity
+            let (a, b) = match g.v[0] == 0 || g.v[1] == 0 {
+                true => (2, 3),
+                false => (0, 1),
+            };
+            g.v.swap(a, b);
+            assert!(!is_solvable(
+                &g,
+                &Grid::create_solved_grid(g.width, g.height)
+            ));
+        }
+    }
+    let line = |v: Vec<i32>, x| Grid {
+        width: 4,
+        height: 1,
+        v,
+        zero: Complex::new(x, 0),
+    };
+    let goal = Grid::create_solved_grid(4, 1);
+    assert!(is_solvable(&line(vec![1, 0, 2, 3], 1), &goal));
+    assert!(!is_solvable(&line(vec![2, 1, 3, 0], 3), &goal));
+}
+
+#[test]
+fn solve_rectangle() {
+    for (width, height) in [(3, 5), (5, 3), (2, 6)] {
+        let g = Grid::create_solved_grid(width, height).scramble(60);
+        let astar = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::LinearConflict,
+            Algo::Astar,
+            None,
+        )
+        .unwrap();
+        check_sequence(&g, &astar.sequence);
+        for h in [Heuristic::Manhattan, Heuristic::WalkingDistance] {
+            let res = solve(
+                &g,
+                &Grid::create_solved_grid(g.width, g.height),
+                h,
+                Algo::IDAstar,
+                None,
+            )
+            .unwrap();
+            assert!(res.sequence.len() == astar.sequence.len());
+            check_sequence(&g, &res.sequence);
+        }
+    }
+}
+
+#[test]
+fn goal_layouts() {
+    // two moves from the classic 8-puzzle goal, which the snail cannot reach
+    let goal = Grid::create_goal(3, 3, Layout::RowMajor);
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![1, 2, 3, 4, 5, 6, 0, 7, 8],
+        zero: Complex::new(0, 2),
+    };
+    assert!(is_solvable(&g, &goal));
+    assert!(!is_solvable(&g, &Grid::create_solved_grid(3, 3)));
+    let res = solve(&g, &goal, Heuristic::LinearConflict, Algo::Astar, None).unwrap();
+    assert!(res.sequence.len() == 2);
+
+    for layout in [Layout::RowMajor, Layout::ColumnMajor] {
+        let goal = Grid::create_goal(3, 4, layout);
+        let g = goal.scramble(100);
+        let astar = solve(&g, &goal, Heuristic::LinearConflict, Algo::Astar, None).unwrap();
+        for h in [Heuristic::WalkingDistance, Heuristic::Manhattan] {
+            let res = solve(&g, &goal, h, Algo::IDAstar, None).unwrap();
+            assert!(res.sequence.len() == astar.sequence.len());
+        }
+        let mut end = g.clone();
+        for &d in astar.sequence.iter() {
+            end.op(d);
+        }
+        assert!(end.v == goal.v);
+    }
+
+    // any reachable layout is a goal
+    let goal = Grid::create_solved_grid(3, 3).scramble(1000);
+    let g = goal.scramble(100);
+    assert!(is_solvable(&g, &goal));
+    let res = solve(&g, &goal, Heuristic::Manhattan, Algo::Astar, None).unwrap();
+    let mut end = g.clone();
+    for &d in res.sequence.iter() {
+        end.op(d);
+    }
+    assert!(end.v == goal.v);
+}
+
+fn check_sequence(g: &Grid, sequence: &[Complex<i32>]) {
     let mut g = g.clone();
     for &op in sequence.iter() {
         g.op(op);
     }
-    assert!(g.v == Grid::create_solved_grid(g.size).v);
+    assert!(g.v == Grid::create_solved_grid(g.width, g.height).v);
+}
+
+// legal moves to the snail goal, as few as those A* finds
+pub(super) fn check_optimal(g: &Grid, res: &Res) {
+    let goal = Grid::create_solved_grid(g.width, g.height);
+    let optimal = solve(g, &goal, Heuristic::Manhattan, Algo::Astar, None).unwrap();
+    assert!(res.sequence.len() == optimal.sequence.len());
+    let mut g = g.clone();
+    for &op in res.sequence.iter() {
+        assert!(g.is_op_legal(op));
+        g.op(op);
+    }
+    assert!(g.v == goal.v);
+}
+
+#[test]
+fn solved() {
+    let g = Grid::create_solved_grid(3, 3);
+    for algo in [
+        Algo::Astar,
+        Algo::IDAstar,
+        Algo::WeightedAstar(2.),
+        Algo::Greedy,
+        Algo::UniformCost,
+        Algo::Bidirectional,
+        Algo::SmaStar(1),
+        Algo::Rbfs,
+        Algo::ParallelIDAstar(4),
+        Algo::HDAstar(4),
+        Algo::Beam(1),
+        Algo::Constructive,
+    ] {
+        let res = solve(&g, &g, Heuristic::Manhattan, algo, None).unwrap();
+        assert!(res.sequence.is_empty());
+    }
+    let res = solve_anytime(&g, &g, Heuristic::Manhattan, 5., None, |_, _| {}).unwrap();
+    assert!(res.sequence.is_empty());
+}
+
+fn test_solve_with(g: &Grid, target: usize, h: Heuristic) {
+    for &alg in &[Algo::Astar, Algo::IDAstar] {
+        let res = solve(
+            g,
+            &Grid::create_solved_grid(g.width, g.height),
+            h,
+            alg,
+            None,
+        )
+        .unwrap();
+        assert!(res.sequence.len() == target);
+        check_sequence(g, &res.sequence);
+    }
 }
 
 fn test_solve(g: &Grid, target: usize) {
@@ -59,31 +296,291 @@ fn test_solve(g: &Grid, target: usize) {
         Heuristic::Manhattan,
         Heuristic::Euclidian,
         Heuristic::Misplaced,
+        Heuristic::LinearConflict,
+        Heuristic::WalkingDistance,
     ] {
-        for &alg in &[Algo::Astar, Algo::IDAstar] {
-            let res = solve(&g, h, alg).unwrap();
-            assert!(res.sequence.len() == target);
-            check_sequence(g, &res.sequence);
-        }
+        test_solve_with(g, target, h);
     }
 }
 
 #[test]
 fn solve_3() {
     let g = Grid {
-        size: 3,
+        width: 3,
+        height: 3,
         v: vec![3, 6, 1, 2, 4, 5, 8, 7, 0],
         zero: Complex::new(2, 2),
     };
     test_solve(&g, 18);
+    // the 6-6-3 database of the 4x4 is too slow to build in a debug test
+    test_solve_with(&g, 18, Heuristic::PatternDatabase);
 }
 
 #[test]
 fn solve_4() {
     let g = Grid {
-        size: 4,
+        width: 4,
+        height: 4,
         v: vec![12, 1, 2, 4, 11, 13, 6, 5, 10, 9, 3, 0, 8, 15, 7, 14],
         zero: Complex::new(3, 2),
     };
     test_solve(&g, 22);
 }
+
+#[test]
+fn linear_conflict() {
+    let hcost = Hcost::new(&Grid::create_solved_grid(3, 3), Heuristic::LinearConflict);
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![2, 1, 3, 8, 0, 4, 7, 6, 5],
+        zero: Complex::new(1, 1),
+    };
+    assert!(hcost.hcost(&g) == 4);
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![3, 2, 1, 8, 0, 4, 7, 6, 5],
+        zero: Complex::new(1, 1),
+    };
+    assert!(hcost.hcost(&g) == 8);
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![1, 2, 3, 7, 0, 4, 8, 6, 5],
+        zero: Complex::new(1, 1),
+    };
+    assert!(hcost.hcost(&g) == 4);
+}
+
+#[test]
+fn linear_conflict_smart_hcost() {
+    check_smart_hcost(Heuristic::LinearConflict);
+}
+
+fn check_smart_hcost(h: Heuristic) {
+    let hcost = Hcost::new(&Grid::create_solved_grid(4, 4), h);
+    let mut s = State {
+        grid: Grid::create_solved_grid(4, 4).scramble(200),
+        h_cost: 0,
+        g_cost: 0,
+        f_cost: 0.,
+        last_op: None,
+        wd: [0; 2],
+    };
+    hcost.init_hcost(&mut s);
+    for _ in 0..200 {
+        let d = *Grid::dirs()
+            .iter()
+            .find(|d| s.grid.is_op_legal(**d) && Some(**d * -1) != s.last_op)
+            .unwrap();
+        let mut ns = s.clone();
+        ns.grid.op(d);
+        ns.last_op = Some(d);
+        hcost.smart_hcost(&s, &mut ns);
+        assert!(ns.h_cost == hcost.hcost(&ns.grid));
+        s = ns;
+    }
+}
+
+#[test]
+fn walking_distance_smart_hcost() {
+    check_smart_hcost(Heuristic::WalkingDistance);
+}
+
+#[test]
+fn solve_uniform_cost() {
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![3, 6, 1, 2, 4, 5, 8, 7, 0],
+        zero: Complex::new(2, 2),
+    };
+    let res = solve(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height),
+        Heuristic::Manhattan,
+        Algo::UniformCost,
+        None,
+    )
+    .unwrap();
+    assert!(res.sequence.len() == 18);
+    check_sequence(&g, &res.sequence);
+    let astar = solve(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height),
+        Heuristic::Manhattan,
+        Algo::Astar,
+        None,
+    )
+    .unwrap();
+    assert!(res.time_complexity > astar.time_complexity);
+}
+
+#[test]
+fn solve_weighted() {
+    let g = Grid {
+        width: 4,
+        height: 4,
+        v: vec![12, 1, 2, 4, 11, 13, 6, 5, 10, 9, 3, 0, 8, 15, 7, 14],
+        zero: Complex::new(3, 2),
+    };
+    let res = solve(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height),
+        Heuristic::Manhattan,
+        Algo::WeightedAstar(1.),
+        None,
+    )
+    .unwrap();
+    assert!(res.sequence.len() == 22);
+    for &w in &[1.5, 2.5, 5.] {
+        let res = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Manhattan,
+            Algo::WeightedAstar(w),
+            None,
+        )
+        .unwrap();
+        assert!(res.sequence.len() >= 22);
+        assert!(res.sequence.len() as f64 <= 22. * w);
+        check_sequence(&g, &res.sequence);
+    }
+}
+
+#[test]
+fn solve_greedy() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(5, 5).scramble(1000);
+        let res = solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::LinearConflict,
+            Algo::Greedy,
+            None,
+        )
+        .unwrap();
+        check_sequence(&g, &res.sequence);
+    }
+}
+
+#[test]
+fn solve_rbfs() {
+    for _ in 0..10 {
+        let g = Grid::create_solved_grid(3, 3).scramble(200);
+        for &h in &[Heuristic::Manhattan, Heuristic::Euclidian] {
+            let res = solve(
+                &g,
+                &Grid::create_solved_grid(g.width, g.height),
+                h,
+                Algo::Rbfs,
+                None,
+            )
+            .unwrap();
+            let astar = solve(
+                &g,
+                &Grid::create_solved_grid(g.width, g.height),
+                h,
+                Algo::Astar,
+                None,
+            )
+            .unwrap();
+            assert!(res.sequence.len() == astar.sequence.len());
+            check_sequence(&g, &res.sequence);
+        }
+    }
+}
+
+#[test]
+fn timeout() {
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![3, 6, 1, 2, 4, 5, 8, 7, 0],
+        zero: Complex::new(2, 2),
+    };
+    for &alg in &[Algo::Astar, Algo::IDAstar] {
+        match solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Zero,
+            alg,
+            Some(Duration::ZERO),
+        ) {
+            Err(SolveError::TimedOut(p)) => {
+                assert!(p.f_bound.unwrap() <= 18);
+                assert!(p.time_complexity >= Stop::PERIOD);
+            }
+            _ => panic!(),
+        }
+    }
+    for &alg in &[Algo::WeightedAstar(2.), Algo::Greedy] {
+        match solve(
+            &g,
+            &Grid::create_solved_grid(g.width, g.height),
+            Heuristic::Zero,
+            alg,
+            Some(Duration::ZERO),
+        ) {
+            Err(SolveError::TimedOut(p)) => assert!(p.f_bound.is_none()),
+            _ => panic!(),
+        }
+    }
+    assert!(solve(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height),
+        Heuristic::Manhattan,
+        Algo::Astar,
+        Some(Duration::from_secs(60))
+    )
+    .is_ok());
+}
+
+#[test]
+fn display_long() {
+    let grid = Grid::create_solved_grid(600, 600);
+    let d = *Grid::dirs().iter().find(|d| grid.is_op_legal(**d)).unwrap();
+    let res = Res {
+        time_complexity: 0,
+        size_complexity: 0,
+        sequence: vec![d, d * -1, d],
+        lower_bound: None,
+        grid: grid.clone(),
+    };
+    let mut end = grid.clone();
+    end.op(d);
+    let s = res.to_string();
+    assert!(s.contains(&format!(
+        "{:?}\n\n... 2 boards left out ...\n\n{:?}\n\n",
+        grid, end
+    )));
+}
+
+#[test]
+fn json() {
+    let g = Grid {
+        width: 3,
+        height: 3,
+        v: vec![1, 2, 3, 8, 4, 0, 7, 6, 5],
+        zero: Complex::new(2, 1),
+    };
+    let res = solve(
+        &g,
+        &Grid::create_solved_grid(g.width, g.height),
+        Heuristic::Manhattan,
+        Algo::Astar,
+        None,
+    )
+    .unwrap();
+    let v = res.to_json("astar", "manhattan", Duration::from_millis(3));
+    assert!(v["algorithm"] == "astar");
+    assert!(v["heuristic"] == "manhattan");
+    assert!(v["input"] == json!([[1, 2, 3], [8, 4, 0], [7, 6, 5]]));
+    assert!(v["goal"] == json!([[1, 2, 3], [8, 0, 4], [7, 6, 5]]));
+    assert!(v["moves"]["tiles"] == json!([4]));
+    assert!(v["moves"]["directions"] == json!(["L"]));
+    assert!(v["solution_length"] == 1);
+    assert!(v["lower_bound"].is_null());
+    assert!(v["wall_time_ms"] == 3.);
+}
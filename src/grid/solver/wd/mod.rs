@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use super::super::Grid;
+use crate::complex::Complex;
+
+#[cfg(test)]
+mod tests;
+
+// Takahashi's walking distance for one axis : a state counts, for every line,
+// how many tiles of each goal line it holds, plus the line of the blank. Every
+// reachable state is found by a breadth-first search from the goal.
+struct Table {
+    lines: usize,
+    line: fn(Complex<i32>) -> i32,
+    goal_line: Vec<u8>,
+    index: HashMap<Vec<u8>, u32>,
+    dist: Vec<u8>,
+    next: Vec<u32>,
+}
+
+impl Table {
+    fn build(goal: &Grid, lines: i32, line: fn(Complex<i32>) -> i32) -> Self {
+        let n = lines as usize;
+        let mut table = Self {
+            lines: n,
+            line,
+            goal_line: vec![0; goal.v.len()],
+            index: HashMap::new(),
+            dist: vec![0],
+            next: Vec::new(),
+        };
+        for y in 0..goal.height {
+            for x in 0..goal.width {
+                let p = Complex::new(x, y);
+                table.goal_line[*goal.get_cell_ref(p) as usize] = line(p) as u8;
+            }
+        }
+
+        let mut states = vec![table.matrix(goal)];
+        table.index.insert(states[0].clone(), 0);
+        // states are numbered in discovery order, which is the queue order
+        let mut i = 0;
+        while i < states.len() {
+            let blank = states[i][n * n] as usize;
+            for b in [blank.wrapping_sub(1), blank + 1] {
+                for g in 0..n {
+                    if b >= n || states[i][b * n + g] == 0 {
+                        table.next.push(u32::MAX);
+                        continue;
+                    }
+                    let mut s = states[i].clone();
+                    s[b * n + g] -= 1;
+                    s[blank * n + g] += 1;
+                    s[n * n] = b as u8;
+                    let j = match table.index.get(&s) {
+                        Some(&j) => j,
+                        None => {
+                            let j = states.len() as u32;
+                            table.index.insert(s.clone(), j);
+                            table.dist.push(table.dist[i] + 1);
+                            states.push(s);
+                            j
+                        }
+                    };
+                    table.next.push(j);
+                }
+            }
+            i += 1;
+        }
+        table
+    }
+
+    fn matrix(&self, grid: &Grid) -> Vec<u8> {
+        let n = self.lines;
+        let mut m = vec![0; n * n + 1];
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let p = Complex::new(x, y);
+                let tile = *grid.get_cell_ref(p) as usize;
+                if tile != 0 {
+                    m[(self.line)(p) as usize * n + self.goal_line[tile] as usize] += 1;
+                }
+            }
+        }
+        m[n * n] = (self.line)(grid.zero) as u8;
+        m
+    }
+
+    fn key(&self, grid: &Grid) -> u32 {
+        self.index[&self.matrix(grid)]
+    }
+
+    // the blank moves one line down if `forward`, up otherwise, swapping
+    // places with `tile`
+    fn step(&self, key: u32, forward: bool, tile: i32) -> u32 {
+        let i = (key as usize * 2 + forward as usize) * self.lines;
+        self.next[i + self.goal_line[tile as usize] as usize]
+    }
+}
+
+pub struct WalkingDistance {
+    vertical: Table,
+    horizontal: Table,
+}
+
+impl WalkingDistance {
+    pub fn new(goal: &Grid) -> Self {
+        Self {
+            vertical: Table::build(goal, goal.height, |p| p.y),
+            horizontal: Table::build(goal, goal.width, |p| p.x),
+        }
+    }
+
+    pub fn keys(&self, grid: &Grid) -> [u32; 2] {
+        [self.vertical.key(grid), self.horizontal.key(grid)]
+    }
+
+    pub fn cost(&self, keys: [u32; 2]) -> i32 {
+        self.vertical.dist[keys[0] as usize] as i32 + self.horizontal.dist[keys[1] as usize] as i32
+    }
+
+    // `grid` is taken before the blank moves along `d`
+    pub fn step(&self, keys: [u32; 2], grid: &Grid, d: Complex<i32>) -> [u32; 2] {
+        let tile = *grid.get_cell_ref(grid.zero + d);
+        match d.y {
+            0 => [keys[0], self.horizontal.step(keys[1], d.x > 0, tile)],
+            _ => [self.vertical.step(keys[0], d.y > 0, tile), keys[1]],
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use super::WalkingDistance;
+use crate::grid::{
+    solver::{Hcost, Heuristic},
+    Grid,
+};
+
+#[test]
+fn solved_is_zero() {
+    for size in 2..5 {
+        let g = Grid::create_solved_grid(size, size);
+        let wd = WalkingDistance::new(&g);
+        assert!(wd.cost(wd.keys(&g)) == 0);
+    }
+}
+
+#[test]
+fn rectangle() {
+    let goal = Grid::create_solved_grid(3, 5);
+    let wd = WalkingDistance::new(&goal);
+    let manhattan = Hcost::new(&goal, Heuristic::Manhattan);
+    assert!(wd.cost(wd.keys(&goal)) == 0);
+    for _ in 0..50 {
+        let g = Grid::create_solved_grid(3, 5).scramble(100);
+        assert!(wd.cost(wd.keys(&g)) >= manhattan.hcost(&g));
+    }
+}
+
+#[test]
+fn table_size() {
+    let wd = WalkingDistance::new(&Grid::create_solved_grid(4, 4));
+    assert!(wd.vertical.dist.len() == 24964);
+    assert!(wd.horizontal.dist.len() == 24964);
+}
+
+#[test]
+fn dominates_manhattan() {
+    let wd = WalkingDistance::new(&Grid::create_solved_grid(4, 4));
+    let manhattan = Hcost::new(&Grid::create_solved_grid(4, 4), Heuristic::Manhattan);
+    for _ in 0..50 {
+        let g = Grid::create_solved_grid(4, 4).scramble(100);
+        assert!(wd.cost(wd.keys(&g)) >= manhattan.hcost(&g));
+    }
+}
@@ -1,32 +1,102 @@
+use std::collections::HashMap;
+
 use crate::complex::Complex;
 
-use super::Grid;
+use super::{solver::solvability, Grid, Layout};
 
 #[test]
 fn create_solved_grid() {
-    let g = Grid::create_solved_grid(3);
-    assert!(g.size == 3);
+    let g = Grid::create_solved_grid(3, 3);
+    assert!((g.width, g.height) == (3, 3));
     assert!(g.v == vec![1, 2, 3, 8, 0, 4, 7, 6, 5]);
     assert!(g.zero == Complex::new(1, 1));
 }
 
+#[test]
+fn create_solved_rectangle() {
+    let g = Grid::create_solved_grid(3, 5);
+    assert!(g.v == vec![1, 2, 3, 12, 13, 4, 11, 14, 5, 10, 0, 6, 9, 8, 7]);
+    assert!(g.zero == Complex::new(1, 3));
+    let g = Grid::create_solved_grid(5, 3);
+    assert!(g.v == vec![1, 2, 3, 4, 5, 12, 13, 14, 0, 6, 11, 10, 9, 8, 7]);
+    assert!(g.zero == Complex::new(3, 1));
+    let g = Grid::create_solved_grid(1, 4);
+    assert!(g.v == vec![1, 2, 3, 0]);
+    assert!(g.zero == Complex::new(0, 3));
+}
+
+#[test]
+fn create_goal() {
+    let g = Grid::create_goal(3, 3, Layout::RowMajor);
+    assert!(g.v == vec![1, 2, 3, 4, 5, 6, 7, 8, 0]);
+    assert!(g.zero == Complex::new(2, 2));
+    let g = Grid::create_goal(3, 2, Layout::ColumnMajor);
+    assert!(g.v == vec![1, 3, 5, 2, 4, 0]);
+    assert!(g.zero == Complex::new(2, 1));
+    let g = Grid::create_goal(3, 3, Layout::Snail);
+    assert!(g.v == Grid::create_solved_grid(3, 3).v);
+}
+
+#[test]
+fn create_random_rectangle() {
+    let g = Grid::create_solved_grid(4, 6).scramble(1000);
+    assert!((g.width, g.height) == (4, 6));
+    assert!(g.v[(g.zero.y * 4 + g.zero.x) as usize] == 0);
+    let g = Grid::create_solved_grid(1, 1).scramble(10);
+    assert!(g.v == vec![0]);
+}
+
+#[test]
+fn shuffle() {
+    for (width, height) in [(2, 2), (3, 3), (4, 4), (3, 5), (1, 4), (5, 1)] {
+        for layout in [Layout::Snail, Layout::RowMajor] {
+            let goal = Grid::create_goal(width, height, layout);
+            for _ in 0..20 {
+                let g = goal.shuffle(true).unwrap();
+                assert!(g.v[(g.zero.y * width + g.zero.x) as usize] == 0);
+                assert!(solvability(&g, &goal).is_ok());
+                let g = goal.shuffle(false).unwrap();
+                assert!(g.v[(g.zero.y * width + g.zero.x) as usize] == 0);
+                assert!(solvability(&g, &goal).is_err());
+            }
+        }
+    }
+    // a blank and a single tile can only slide
+    assert!(Grid::create_solved_grid(2, 1).shuffle(false).is_none());
+    assert!(Grid::create_solved_grid(1, 1).shuffle(true).unwrap().v == vec![0]);
+}
+
+#[test]
+fn shuffle_uniform() {
+    // the 2x2 puzzle has 12 reachable arrangements and 12 unreachable ones
+    let goal = Grid::create_solved_grid(2, 2);
+    for solvable in [true, false] {
+        let mut seen = HashMap::new();
+        for _ in 0..12000 {
+            *seen.entry(goal.shuffle(solvable).unwrap().v).or_insert(0) += 1;
+        }
+        assert!(seen.len() == 12);
+        assert!(seen.values().all(|&n| (700..1300).contains(&n)));
+    }
+}
+
 #[test]
 fn get_cell_ref() {
-    let g = Grid::create_solved_grid(3);
+    let g = Grid::create_solved_grid(3, 3);
     assert!(*g.get_cell_ref(Complex::new(2, 0)) == 3);
     assert!(*g.get_cell_ref(Complex::new(0, 2)) == 7);
 }
 
 #[test]
 fn get_cell_mut() {
-    let mut g = Grid::create_solved_grid(3);
+    let mut g = Grid::create_solved_grid(3, 3);
     assert!(*g.get_cell_mut(Complex::new(2, 0)) == 3);
     assert!(*g.get_cell_mut(Complex::new(0, 2)) == 7);
 }
 
 #[test]
 fn op() {
-    let mut g = Grid::create_solved_grid(3);
+    let mut g = Grid::create_solved_grid(3, 3);
     g.op(Complex::new(0, -1));
     assert!(g.v == vec![1, 0, 3, 8, 2, 4, 7, 6, 5]);
     g.op(Complex::new(1, 0));
@@ -35,7 +105,7 @@ fn op() {
 
 #[test]
 fn is_op_legal() {
-    let mut g = Grid::create_solved_grid(3);
+    let mut g = Grid::create_solved_grid(3, 3);
     assert!(Grid::dirs().iter().all(|d| g.is_op_legal(*d)));
     g.op(Complex::new(0, 1));
     assert!(!g.is_op_legal(Complex::new(0, 1)));
@@ -43,4 +113,9 @@ fn is_op_legal() {
     assert!(!g.is_op_legal(Complex::new(1, 0)));
     assert!(g.is_op_legal(Complex::new(-1, 0)));
     assert!(g.is_op_legal(Complex::new(0, -1)));
+
+    let mut g = Grid::create_solved_grid(2, 4);
+    g.op(Complex::new(1, 0));
+    assert!(!g.is_op_legal(Complex::new(1, 0)));
+    assert!(g.is_op_legal(Complex::new(0, 1)));
 }
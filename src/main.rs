@@ -1,66 +1,526 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter},
+    time::{Duration, Instant},
+};
 
 use clap::{self, ArgGroup, Parser};
 use grid::{
-    solver::{solve, Algo, Heuristic},
-    Grid,
+    moves::parse_moves,
+    parser::{format, parse},
+    solver::{
+        interrupt,
+        layer::at_distance,
+        optimize,
+        pdb::{Pdb, PdbError},
+        solvability, solve, solve_anytime, Algo, Heuristic, SolveError,
+    },
+    Grid, Layout,
 };
 
 mod complex;
 mod grid;
 
 #[derive(clap::Parser)]
-#[command(group(ArgGroup::new("input").required(true).args(&["generate", "filepath"])))]
 struct Args {
-    #[arg(long, value_parser = ["manhattan", "euclidian", "misplaced", "zero"])]
-    heuristic: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    Solve(SolveArgs),
+    #[command(group(ArgGroup::new("scramble").required(true).args(&["iterations", "random", "distance"])))]
+    Generate {
+        #[arg(long, short, value_parser = parse_dims)]
+        size: (i32, i32),
+
+        #[arg(long, short)]
+        iterations: Option<usize>,
+
+        #[arg(long, short)]
+        random: bool,
+
+        #[arg(long, requires = "random")]
+        unsolvable: bool,
+
+        #[arg(long, short)]
+        distance: Option<usize>,
+
+        #[arg(long, short)]
+        output: Option<String>,
+
+        #[command(flatten)]
+        goal: GoalArgs,
+    },
+    Check {
+        #[arg(long, short)]
+        file: String,
 
-    #[arg(long, short, requires = "iterations")]
-    generate: Option<usize>,
+        #[command(flatten)]
+        goal: GoalArgs,
+    },
+    Verify {
+        #[arg(long, short)]
+        file: String,
+
+        #[arg(long, short)]
+        moves: String,
+
+        #[command(flatten)]
+        goal: GoalArgs,
+    },
+    Bench(BenchArgs),
+    Pdb {
+        #[command(subcommand)]
+        command: PdbCommand,
+    },
+}
+
+#[derive(clap::Args)]
+#[command(group(ArgGroup::new("input").required(true).args(&["generate", "filepath"])))]
+#[command(group(ArgGroup::new("scramble").args(&["iterations", "random"])))]
+struct SolveArgs {
+    #[arg(long, short, requires = "scramble", value_parser = parse_dims)]
+    generate: Option<(i32, i32)>,
 
     #[arg(long, short, requires = "generate")]
     iterations: Option<usize>,
 
-    #[arg(long, short, conflicts_with_all = &["generate", "iterations"])]
+    #[arg(long, short, requires = "generate")]
+    random: bool,
+
+    #[arg(long, short, conflicts_with_all = &["generate", "iterations", "random"])]
     filepath: Option<String>,
 
-    #[arg(long, short, value_parser = ["astar", "idastar"], default_value = "astar")]
+    #[command(flatten)]
+    solver: SolverArgs,
+
+    #[command(flatten)]
+    goal: GoalArgs,
+
+    #[arg(long)]
+    optimize: Option<usize>,
+
+    #[arg(long, value_parser = ["text", "json"], default_value = "text")]
+    output: String,
+}
+
+#[derive(clap::Args)]
+#[command(group(ArgGroup::new("scramble").required(true).args(&["iterations", "random"])))]
+struct BenchArgs {
+    #[arg(long, short, value_parser = parse_dims)]
+    size: (i32, i32),
+
+    #[arg(long, short)]
+    iterations: Option<usize>,
+
+    #[arg(long, short)]
+    random: bool,
+
+    #[arg(long, short, default_value = "10")]
+    count: usize,
+
+    #[command(flatten)]
+    solver: SolverArgs,
+
+    #[command(flatten)]
+    goal: GoalArgs,
+}
+
+#[derive(clap::Args)]
+struct GoalArgs {
+    #[arg(long, value_parser = ["snail", "row-major", "column-major"], default_value = "snail", conflicts_with = "goal")]
+    layout: String,
+
+    #[arg(long)]
+    goal: Option<String>,
+}
+
+impl GoalArgs {
+    fn goal(&self, (width, height): (i32, i32)) -> Result<Grid, Box<dyn Error>> {
+        let Some(path) = &self.goal else {
+            let layout = match self.layout.as_str() {
+                "snail" => Layout::Snail,
+                "row-major" => Layout::RowMajor,
+                "column-major" => Layout::ColumnMajor,
+                _ => unreachable!(),
+            };
+            return Ok(Grid::create_goal(width, height, layout));
+        };
+        let goal = parse(&std::fs::read_to_string(path)?)?;
+        if goal.dims() != (width, height) {
+            return Err(CliError::GoalDims(goal.dims(), (width, height)).into());
+        }
+        Ok(goal)
+    }
+}
+
+#[derive(clap::Args)]
+struct SolverArgs {
+    #[arg(long, value_parser = ["manhattan", "euclidian", "misplaced", "linear-conflict", "pattern-database", "walking-distance", "zero"])]
+    heuristic: String,
+
+    #[arg(long, short, value_parser = ["astar", "idastar", "weighted-astar", "greedy", "uniform-cost", "bidirectional", "smastar", "rbfs", "parallel-idastar", "hdastar", "anytime", "beam", "constructive"], default_value = "astar")]
     algo: String,
+
+    #[arg(long, short, required_if_eq("algo", "weighted-astar"))]
+    weight: Option<f64>,
+
+    #[arg(long, required_if_eq("algo", "smastar"))]
+    max_states: Option<usize>,
+
+    #[arg(long, short)]
+    threads: Option<usize>,
+
+    #[arg(long, value_parser = parse_duration)]
+    timeout: Option<Duration>,
+
+    #[arg(long, required_if_eq("algo", "beam"))]
+    beam_width: Option<usize>,
+
+    #[arg(long)]
+    pdb: Option<String>,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+impl SolverArgs {
+    fn heuristic(&self) -> Heuristic {
+        match self.heuristic.as_str() {
+            "manhattan" => Heuristic::Manhattan,
+            "euclidian" => Heuristic::Euclidian,
+            "misplaced" => Heuristic::Misplaced,
+            "linear-conflict" => Heuristic::LinearConflict,
+            "pattern-database" => Heuristic::PatternDatabase,
+            "walking-distance" => Heuristic::WalkingDistance,
+            "zero" => Heuristic::Zero,
+            _ => unreachable!(),
+        }
+    }
 
-    let g = match (args.filepath, args.generate, args.iterations) {
-        (Some(filepath), None, None) => match std::fs::read_to_string(filepath) {
-            Ok(raw) => match grid::parser::parse(raw.as_str()) {
-                Ok(g) => g,
-                Err(e) => return Err(e.into()),
-            },
-            Err(e) => return Err(e.into()),
-        },
-        (None, Some(size), Some(n)) => Grid::create_random_grid(size as i32, n as i32),
-        _ => unreachable!(),
+    // anytime is not an `Algo`, it goes through `solve_anytime`
+    fn algo(&self) -> Result<Algo, Box<dyn Error>> {
+        let threads = match self.threads {
+            Some(threads) => threads,
+            None => std::thread::available_parallelism()?.get(),
+        };
+        Ok(match self.algo.as_str() {
+            "astar" => Algo::Astar,
+            "idastar" => Algo::IDAstar,
+            "weighted-astar" => Algo::WeightedAstar(self.weight.unwrap()),
+            "greedy" => Algo::Greedy,
+            "uniform-cost" => Algo::UniformCost,
+            "bidirectional" => Algo::Bidirectional,
+            "smastar" => Algo::SmaStar(self.max_states.unwrap()),
+            "rbfs" => Algo::Rbfs,
+            "parallel-idastar" => Algo::ParallelIDAstar(threads),
+            "hdastar" => Algo::HDAstar(threads),
+            "beam" => Algo::Beam(self.beam_width.unwrap()),
+            "constructive" => Algo::Constructive,
+            _ => unreachable!(),
+        })
+    }
+
+    // without a file the default database is built now, before Ctrl-C is
+    // caught, as building it cannot be interrupted
+    fn load_pdb(&self, goal: &Grid) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = &self.pdb {
+            let pdb = Pdb::load(BufReader::new(File::open(path)?))?;
+            if !pdb.fits(goal) {
+                return Err(PdbError::GoalMismatch.into());
+            }
+            pdb.register();
+        } else if let Heuristic::PatternDatabase = self.heuristic() {
+            Pdb::get(goal)?;
+        }
+        Ok(())
+    }
+
+    // the other searches would ignore a timeout or a Ctrl-C
+    fn stoppable(&self) -> Result<bool, Box<dyn Error>> {
+        let stoppable = self.algo == "anytime" || self.algo()?.stoppable();
+        if !stoppable && self.timeout.is_some() {
+            return Err(CliError::Timeout(self.algo.clone()).into());
+        }
+        Ok(stoppable)
+    }
+}
+
+#[derive(clap::Subcommand)]
+enum PdbCommand {
+    Build {
+        #[arg(long, short, value_parser = parse_dims)]
+        size: (i32, i32),
+
+        #[arg(long, short)]
+        output: String,
+
+        #[command(flatten)]
+        goal: GoalArgs,
+    },
+}
+
+enum CliError {
+    GoalDims((i32, i32), (i32, i32)),
+    AlwaysSolvable((i32, i32)),
+    TooFar((i32, i32), usize),
+    AnytimeBench,
+    Timeout(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GoalDims((w, h), (width, height)) => write!(
+                f,
+                "the goal is {}x{} but the puzzle is {}x{}",
+                w, h, width, height
+            ),
+            Self::AlwaysSolvable((w, h)) => write!(f, "every {}x{} puzzle is solvable", w, h),
+            Self::TooFar((w, h), d) => {
+                write!(f, "no {}x{} puzzle is {} moves away from the goal", w, h, d)
+            }
+            Self::AnytimeBench => write!(f, "bench does not support the anytime algorithm"),
+            Self::Timeout(algo) => write!(f, "{} does not support a timeout", algo),
+        }
+    }
+}
+
+impl Error for CliError {}
+
+impl fmt::Debug for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", *self)
+    }
+}
+
+// a width and a height as `WxH`, or a single size for a square
+fn parse_dims(s: &str) -> Result<(i32, i32), String> {
+    let dim = |d: &str| match d.parse::<i32>() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err(format!("invalid size `{}`", s)),
     };
+    let (w, h) = match s.split_once('x') {
+        Some((w, h)) => (dim(w)?, dim(h)?),
+        None => dim(s).map(|n| (n, n))?,
+    };
+    match w.checked_mul(h) {
+        Some(_) => Ok((w, h)),
+        None => Err(format!("size `{}` is too large", s)),
+    }
+}
 
-    let h = match args.heuristic.as_str() {
-        "manhattan" => Heuristic::Manhattan,
-        "euclidian" => Heuristic::Euclidian,
-        "misplaced" => Heuristic::Misplaced,
-        "zero" => Heuristic::Zero,
-        _ => unreachable!(),
+// `n` random moves away from the goal, or uniformly random without `n`
+fn scramble(goal: &Grid, iterations: Option<usize>) -> Grid {
+    match iterations {
+        Some(n) => goal.scramble(n as i32),
+        None => goal.shuffle(true).unwrap(),
+    }
+}
+
+// a number of seconds, or of the unit that follows : ms, s, m or h
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let i = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let n: f64 = s[..i]
+        .parse()
+        .map_err(|_| format!("invalid duration `{}`", s))?;
+    let unit = match &s[i..] {
+        "ms" => 0.001,
+        "" | "s" => 1.,
+        "m" => 60.,
+        "h" => 3600.,
+        u => return Err(format!("unknown unit `{}`", u)),
     };
+    Duration::try_from_secs_f64(n * unit).map_err(|e| e.to_string())
+}
 
-    let algo = match args.algo.as_str() {
-        "astar" => Algo::Astar,
-        "idastar" => Algo::IDAstar,
-        _ => unreachable!(),
+extern "C" fn on_interrupt(_: libc::c_int) {
+    interrupt();
+    // a second Ctrl-C kills the program right away
+    unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+}
+
+// Ctrl-C stops the searches that listen to it, and kills the program
+// everywhere else
+fn catch_interrupts(catch: bool) {
+    let handler = match catch {
+        true => on_interrupt as *const () as libc::sighandler_t,
+        false => libc::SIG_DFL,
     };
+    unsafe { libc::signal(libc::SIGINT, handler) };
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
 
-    match solve(&g, h, algo) {
-        Ok(res) => println!("{}", res),
-        Err(e) => return Err(e.into()),
+    match args.command {
+        Command::Solve(args) => run_solve(args),
+        Command::Generate {
+            size,
+            iterations,
+            unsolvable,
+            distance,
+            output,
+            goal,
+            ..
+        } => {
+            let goal = goal.goal(size)?;
+            let g = match (unsolvable, distance) {
+                (true, _) => goal.shuffle(false).ok_or(CliError::AlwaysSolvable(size))?,
+                (false, Some(d)) => at_distance(&goal, d)?.ok_or(CliError::TooFar(size, d))?,
+                (false, None) => scramble(&goal, iterations),
+            };
+            let s = format(&g);
+            match output {
+                Some(path) => std::fs::write(path, s)?,
+                None => print!("{}", s),
+            }
+            Ok(())
+        }
+        Command::Check { file, goal } => {
+            let g = parse(&std::fs::read_to_string(file)?)?;
+            if let Err(why) = solvability(&g, &goal.goal(g.dims())?) {
+                println!("unsolvable : {}", why);
+                std::process::exit(1);
+            }
+            println!("solvable");
+            Ok(())
+        }
+        Command::Verify { file, moves, goal } => {
+            let mut g = parse(&std::fs::read_to_string(file)?)?;
+            let goal = goal.goal(g.dims())?;
+            let moves = parse_moves(&moves)?;
+            g.replay(&moves)?;
+            println!("{:?}\n", g);
+            if !g.is_solved(&goal) {
+                println!("goal not reached after {} moves", moves.len());
+                std::process::exit(1);
+            }
+            println!("goal reached in {} moves", moves.len());
+            Ok(())
+        }
+        Command::Bench(args) => run_bench(args),
+        Command::Pdb { command } => {
+            match command {
+                PdbCommand::Build { size, output, goal } => {
+                    let pdb =
+                        Pdb::build(&goal.goal(size)?, Pdb::default_partition(size.0, size.1))?;
+                    pdb.save(BufWriter::new(File::create(output)?))?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_solve(args: SolveArgs) -> Result<(), Box<dyn Error>> {
+    let (g, goal) = match (&args.filepath, args.generate) {
+        (Some(filepath), None) => {
+            let g = parse(&std::fs::read_to_string(filepath)?)?;
+            let goal = args.goal.goal(g.dims())?;
+            (g, goal)
+        }
+        (None, Some(size)) => {
+            let goal = args.goal.goal(size)?;
+            (scramble(&goal, args.iterations), goal)
+        }
+        _ => unreachable!(),
     };
+    let solver = &args.solver;
+    solver.load_pdb(&goal)?;
+    let h = solver.heuristic();
+    catch_interrupts(solver.stoppable()?);
+
+    let json = args.output == "json";
+    let start = Instant::now();
+    if solver.algo == "anytime" {
+        // one JSON document per line, for each improvement
+        solve_anytime(
+            &g,
+            &goal,
+            h,
+            solver.weight.unwrap_or(5.),
+            solver.timeout,
+            |res, bound| match json {
+                true => {
+                    let mut v = res.to_json(&solver.algo, &solver.heuristic, start.elapsed());
+                    v["suboptimality_bound"] = bound.into();
+                    println!("{}", v);
+                }
+                false => {
+                    println!("suboptimality bound : {:.3}", bound);
+                    println!("{}", res);
+                }
+            },
+        )?;
+        return Ok(());
+    }
+
+    let mut res = solve(&g, &goal, h, solver.algo()?, solver.timeout)?;
+    catch_interrupts(false);
+    let saved = args.optimize.map(|k| optimize(&mut res, k));
+    if json {
+        let mut v = res.to_json(&solver.algo, &solver.heuristic, start.elapsed());
+        if let Some(saved) = saved {
+            v["optimization_removed"] = saved.into();
+        }
+        println!("{}", v);
+        return Ok(());
+    }
+    println!("{}", res);
+    if let Some(saved) = saved {
+        println!("optimization removed {} operations", saved);
+    }
+    Ok(())
+}
+
+// solves `count` random puzzles and prints the means over the solved ones
+fn run_bench(args: BenchArgs) -> Result<(), Box<dyn Error>> {
+    let solver = &args.solver;
+    if solver.algo == "anytime" {
+        return Err(CliError::AnytimeBench.into());
+    }
+    let goal = args.goal.goal(args.size)?;
+    solver.load_pdb(&goal)?;
+    let (h, algo) = (solver.heuristic(), solver.algo()?);
+    catch_interrupts(solver.stoppable()?);
+
+    let (mut solved, mut failed) = (0, 0);
+    let (mut length, mut time, mut space) = (0, 0, 0);
+    let (mut wall, mut max_wall) = (Duration::ZERO, Duration::ZERO);
+    for i in 0..args.count {
+        let g = scramble(&goal, args.iterations);
+        let start = Instant::now();
+        match solve(&g, &goal, h, algo, solver.timeout) {
+            Ok(res) => {
+                let elapsed = start.elapsed();
+                solved += 1;
+                length += res.length();
+                time += res.time_complexity();
+                space += res.size_complexity();
+                wall += elapsed;
+                max_wall = max_wall.max(elapsed);
+            }
+            Err(SolveError::Interrupted(_)) => {
+                println!("interrupted after {} puzzles", i);
+                break;
+            }
+            Err(e) => {
+                println!("puzzle {} : {}", i + 1, e);
+                failed += 1;
+            }
+        }
+    }
 
+    println!("puzzles solved : {} ({} failed)", solved, failed);
+    if solved > 0 {
+        let n = solved as f64;
+        println!("mean number of operations : {:.1}", length as f64 / n);
+        println!("mean complexity in time : {:.1}", time as f64 / n);
+        println!("mean complexity in size : {:.1}", space as f64 / n);
+        println!("mean wall time : {:?}", wall / solved);
+        println!("max wall time : {:?}", max_wall);
+    }
     Ok(())
 }